@@ -3,6 +3,7 @@
 """Python types hints for native Rust classes."""
 
 # Builtins.
+from collections.abc import Callable, Iterator
 from pathlib import Path
 
 class MethodMatch:
@@ -28,6 +29,29 @@ class MethodMatch:
     def similarity(self) -> float:
         """Normalized similarity ratio between the two methods."""
 
+    @property
+    def confidence(self) -> float:
+        """Similarity scaled down for small methods, `similarity * (1 - exp(-blocks / k))`.
+
+        Where `blocks` is the block count of the smaller method and `k` the grapher's `confidence_scale`.
+        """
+
+    @property
+    def reference(self) -> str:
+        """Name of the reference binary the clean method belongs to."""
+
+    @property
+    def exact(self) -> bool:
+        """Whether the hashes of both methods matched, rather than their similarity being computed."""
+
+    @property
+    def block_alignment(self) -> list[tuple[int, int, float]] | None:
+        """The (sample_block_index, reference_block_index, similarity) pairing of each sample block.
+
+        Each sample block is paired with its most similar reference block, only recorded if the grapher
+        was created with `block_alignment` enabled.
+        """
+
 class BinaryMatch:
     """Data Model of the similarity between the Control Flow Gaphs (CFG) of two binaries."""
 
@@ -35,6 +59,25 @@ class BinaryMatch:
     def similarity(self) -> float:
         """Normalized similarity ratio between the two binaries."""
 
+    @property
+    def containment(self) -> float:
+        """Fraction of the reference functions whose best sample match reached the threshold.
+
+        Directional metric of how much of the reference is contained within the sample.
+        """
+
+    @property
+    def sample_function_count(self) -> int:
+        """Number of functions of the source binary that were compared, 0 in older reports."""
+
+    @property
+    def reference_function_count(self) -> int:
+        """Number of functions of the dest binary that were compared, 0 in older reports."""
+
+    @property
+    def compute_time_secs(self) -> float:
+        """Time spent comparing the binaries in seconds, 0 in older reports."""
+
     @property
     def source(self) -> str:
         """The name of the source binary during testing."""
@@ -47,9 +90,191 @@ class BinaryMatch:
     def matches(self) -> list[MethodMatch]:
         """Returns the array of match results between both binaries."""
 
+    def method_by_malware_offset(self, offset: int) -> MethodMatch | None:
+        """Returns the method match of the malware method at the given offset, if any.
+
+        Args:
+            offset (int) : Offset of the malware method.
+
+        Returns:
+            MethodMatch | None : The matching method, looked up in linear time.
+        """
+
+    def method_by_clean_offset(self, offset: int) -> MethodMatch | None:
+        """Returns the method match of the clean method at the given offset, if any.
+
+        Args:
+            offset (int) : Offset of the clean method.
+
+        Returns:
+            MethodMatch | None : The matching method, looked up in linear time.
+        """
+
+    def best_method(self) -> MethodMatch | None:
+        """Returns the method match with the highest similarity, if any.
+
+        Ties are broken by the lowest malware offset.
+
+        Returns:
+            MethodMatch | None : The strongest method match, None if there are no matches.
+        """
+
+    def sorted_matches(self) -> list[MethodMatch]:
+        """Returns the method matches by decreasing similarity, NaN similarities last.
+
+        Ties are broken by the lowest malware offset.
+
+        Returns:
+            list[MethodMatch] : The method matches, strongest first.
+        """
+
+class PackageMatch:
+    """Data Model of the matches of a Go package, grouping the method matches by resolved package."""
+
+    @property
+    def package(self) -> str:
+        """The package path of the resolved methods, such as "crypto/tls"."""
+
+    @property
+    def match_count(self) -> int:
+        """Number of method matches resolved to the package."""
+
+    @property
+    def function_count(self) -> int:
+        """Number of distinct package functions among the matches."""
+
+    @property
+    def mean_similarity(self) -> float:
+        """Mean similarity of the method matches resolved to the package."""
+
+class BasicBlock:
+    """Data model of a Control Flow Graph's (CFG) basic block."""
+
+    @property
+    def offset(self) -> int:
+        """Offset of the block relative to the ".text" segment."""
+
+    @property
+    def in_refs(self) -> list[int]:
+        """The list of incoming edges."""
+
+    @property
+    def out_refs(self) -> list[int]:
+        """The list of outgoing edges."""
+
+    @property
+    def hash(self) -> int:
+        """Non-Cryptographic hash of the block's normalized instructions, depends on the normalization mode."""
+
+    def mnemonics(self) -> list[str]:
+        """Returns the disassembly text of each instruction within the block.
+
+        Returns:
+            list[str] : Disassembly text of each instruction.
+        """
+
+    def bytes_hex(self) -> list[str]:
+        """Returns the hex encoded bytes of each instruction within the block.
+
+        Returns:
+            list[str] : Hex encoded bytes of each instruction.
+        """
+
+    def concatenated_bytes(self) -> str:
+        """Returns the block's normalized keys concatenated in order, the exact data its hash is computed from.
+
+        Returns:
+            str : The joined hex encoded bytes of the instructions, or their keys under a normalization mode.
+        """
+
 class ControlFlowGraph:
     """Control Flow Graph (CFG) data model."""
 
+    @property
+    def name(self) -> str:
+        """Name of the function of the Control Flow Graph (CFG)."""
+
+    @property
+    def offset(self) -> int:
+        """Offset of the Control Flow Graph relative to the ".text" segment."""
+
+    @property
+    def blocks(self) -> list[BasicBlock]:
+        """The list of basic blocks withing the Control Flow Graph."""
+
+    @property
+    def hash(self) -> int:
+        """Non-Cryptographic hash of the graph's blocks, depends on the normalization mode."""
+
+    @property
+    def is_exported(self) -> bool:
+        """Whether the function's symbol is global / exported."""
+
+    def entry_block_index(self) -> int | None:
+        """Returns the index of the entry block of the graph.
+
+        The block starting at the function's offset is preferred, otherwise the lowest offset block
+        without predecessors, and failing that the lowest offset block.
+
+        Returns:
+            int | None : Index of the entry block, None if the graph has no blocks.
+        """
+
+    def all_instructions(self) -> list[str]:
+        """Returns every instruction of the graph, flattened across its blocks in ascending offset order.
+
+        Returns:
+            list[str] : The disassembly text of each instruction.
+        """
+
+    def exit_block_indices(self) -> list[int]:
+        """Returns the indices of the exit blocks of the graph (blocks without successors).
+
+        Returns:
+            list[int] : Indices of the exit blocks, in offset order.
+        """
+
+    def to_adjacency_list(self) -> list[tuple[int, list[int]]]:
+        """Returns the adjacency list of the graph, eg: for `networkx.DiGraph(dict(...))`.
+
+        Returns:
+            list[tuple[int, list[int]]] : Each block index along with the indices of its successors.
+        """
+
+    def block_attributes(self) -> list[tuple[int, int]]:
+        """Returns the attributes of each block, by index.
+
+        Returns:
+            list[tuple[int, int]] : The offset and instruction count of each block.
+        """
+
+    def validate(self) -> None:
+        """Checks that the graph's edges are in bounds and symmetric, such as after manipulating its disassembly.
+
+        Raises:
+            ValueError : Describing the first inconsistent edge found.
+        """
+
+    def mnemonic_histogram(self) -> dict[str, int]:
+        """Returns the number of instructions of each mnemonic across the graph's blocks.
+
+        Returns:
+            dict[str, int] : The instruction count of each mnemonic.
+        """
+
+    def diff_blocks(self, reference: ControlFlowGraph) -> BlockAlignment:
+        """Aligns the graph's blocks, as a sample method, one to one with those of a reference method.
+
+        Block pairs are scored by the share of normalized instruction keys they have in common, both
+        graphs should be normalized with the same mode.
+
+        Args:
+            reference (ControlFlowGraph) : The reference method to align with.
+
+        Returns:
+            BlockAlignment : The aligned block pairs, and the blocks only existing on either side.
+        """
+
 class Disassembly:
     """Data Model of a disassembled binary."""
 
@@ -65,110 +290,1143 @@ class Disassembly:
     def graphs(self) -> list[ControlFlowGraph]:
         """The list of Control Flow Graph (CFG) of the disassembly."""
 
-    def __init__(self, sample_path: Path) -> None:
+    @property
+    def format(self) -> str:
+        """Binary format of the disassembled binary (eg: "pe", "elf")."""
+
+    @property
+    def arch(self) -> str:
+        """Architecture of the disassembled binary (eg: "amd64", "arm64")."""
+
+    @property
+    def bits(self) -> int:
+        """Bitness of the disassembled binary."""
+
+    def __len__(self) -> int:
+        """Returns the number of Control Flow Graphs (CFG) of the disassembly."""
+
+    def __iter__(self) -> Iterator[ControlFlowGraph]:
+        """Iterate over the Control Flow Graphs (CFG) of the disassembly, copying them one at a time."""
+
+    def __getitem__(self, index: int) -> ControlFlowGraph:
+        """Returns the Control Flow Graph (CFG) at the given index, negative indices count from the end.
+
+        Raises:
+            IndexError : If the index is out of range.
+        """
+
+    def __init__(
+        self, sample_path: Path, *, high_accuracy: bool = True, resolve_tailcalls: bool = True, parallel_graphs: bool = False
+    ) -> None:
         """Generate the set of Control Flow Graphs (CFG) for the specified binary.
 
         Args:
             sample_path (Path) : Path to the binary to dissassemble.
+            high_accuracy (bool) : Weather functions only reached through discovered candidates are analysed, disabling it is faster but may miss functions.
+            resolve_tailcalls (bool) : Weather tail calls are resolved into separate functions, disabling it is faster but may merge functions.
+            parallel_graphs (bool) : Weather the functions are converted to graphs on all cores, only faster on large binaries.
 
         Returns:
             Disassembly : List of Control Flow Graphs (CFG) of the specified binary.
+
+        Raises:
+            FileAccessError : If the binary could not be read.
+            UnsupportedBinaryFormat : If the binary is not a supported format.
+            DisassemblyFailed : If the binary could not be disassembled, such as a malformed one.
+            DisassemblyPanicked : If the disassembler crashed while processing the binary.
         """
 
-    def filter_symbol(self, search_expression: str) -> Disassembly:
-        """Returns a new Disassembly composed of the Control Flow Graphs (CFG) whose name match the supplied regex.
+    @staticmethod
+    def new_filtered(sample_path: Path, search_expression: str) -> Disassembly:
+        """Generate the Control Flow Graphs (CFG) of the specified binary whose name match the supplied regex.
+
+        The whole binary is still disassembled, only the graphs of the other functions are never built,
+        saving memory rather than disassembly time.
 
         Args:
-            search_expression (str) :  Regex expression used to filter the Disassembly.
+            sample_path (Path) : Path to the binary to dissassemble.
+            search_expression (str) : Regex expression the function names must match.
 
         Returns:
-            Disassembly : New filtered Disassembly instance.
+            Disassembly : The filtered Control Flow Graphs (CFG) of the specified binary.
+
+        Raises:
+            InvalidRegex : If the search expression is not a valid regex.
+            FileAccessError : If the binary could not be read.
+            UnsupportedBinaryFormat : If the binary is not a supported format.
+            DisassemblyFailed : If the binary could not be disassembled, such as a malformed one.
+            DisassemblyPanicked : If the disassembler crashed while processing the binary.
         """
 
-    def get_subset(self, ratio: float) -> Disassembly:
-        """Returns a subset of the disassembly corresponding to the supplied ratio.
+    @staticmethod
+    def supported_formats() -> list[str]:
+        """Returns the short names of the binary formats that can be disassembled (eg: "elf", "pe").
+
+        Returns:
+            list[str] : The supported formats, other formats raise `UnsupportedBinaryFormat`.
+        """
+
+    @staticmethod
+    def list_symbols(sample_path: Path) -> list[tuple[int, str]]:
+        """List the symbols of the specified binary without disassembling it.
 
         Args:
-            ratio (float) : Ratio of the disassembly to keep.
+            sample_path (Path) : Path to the binary to inspect.
 
         Returns:
-            Disassembly : Subset of the original disassembly.
+            list[tuple[int, str]] : The (address, name) of each named symbol, sorted by address.
         """
 
-class CompareReport:
-    """GoGrapher compare report data model."""
+    @staticmethod
+    def merge(name: str, parts: list[Disassembly]) -> Disassembly:
+        """Combine several disassemblies, such as the object files of a library, into a single one.
 
-    @property
-    def sample_name(self) -> str:
-        """The name of the sample this report belongs to."""
+        Graphs overlapping an offset already present in an earlier part are dropped, the path and
+        format are taken from the first part.
 
-    @property
-    def matches(self) -> list[BinaryMatch]:
-        """Returns the list of matches contained in this report by Go version."""
+        Args:
+            name (str) : Name of the merged disassembly.
+            parts (list[Disassembly]) : The disassemblies to combine.
 
-    # TODO: Compute Time
+        Returns:
+            Disassembly : The merged disassembly.
+        """
 
-    def to_json(self) -> str:
-        """Returns the JSON representation the the compare report.
+    def fingerprint(self) -> int:
+        """Non-Cryptographic hash of the whole binary, folded from the sorted hashes of its graphs.
+
+        Independent of the order and offsets of the graphs, dependent on the normalization mode.
 
         Returns:
-            str : JSON representation of the report.
+            int : The fingerprint of the disassembly.
         """
 
-    @staticmethod
-    def from_json(json_data: str) -> CompareReport:
-        """Parse a CompareReport from its JSON representation.
+    def filter_symbol(self, search_expression: str) -> Disassembly:
+        """Returns a new Disassembly composed of the Control Flow Graphs (CFG) whose name match the supplied regex.
 
         Args:
-            json_data (&str) : The JSON data to parse.
+            search_expression (str) :  Regex expression used to filter the Disassembly.
 
         Returns:
-            CompareReport : The newly parsed instance of CompareReport.
+            Disassembly : New filtered Disassembly instance.
+
+        Raises:
+            InvalidRegex : If the search expression is not a valid regex.
         """
 
-class Grapher:
-    """Compute a summary of the similarities between a malware sample and a set of clean libraries."""
+    def exported_only(self) -> Disassembly:
+        """Returns a new Disassembly composed of the Control Flow Graphs (CFG) whose symbol is global / exported.
 
-    def __init__(self, *, threshold: float, display_progress: bool = False) -> None:
-        """Initialize a new GoGrapher instance.
+        Returns:
+            Disassembly : New filtered Disassembly instance.
+        """
+
+    def rebase(self, delta: int) -> Disassembly:
+        """Returns a new Disassembly whose graph and block offsets are shifted by `delta`.
+
+        The instructions keep their original addresses, hashes and similarities are unaffected.
 
         Args:
-            threshold (f32) : Value at which matches are considered significant.
-            display_progress (bool): Weather to output progress updates to the console.
+            delta (int) : Signed amount added to every offset, such as an image base.
 
         Returns:
-            GoGrapher : The newly initialized GoGrapher instance.
+            Disassembly : New rebased Disassembly instance.
         """
 
-    def compare(self, sample_graph: Disassembly, reference_graphs: list[Disassembly]) -> CompareReport:
-        """Compare a malware sample to a clean set of libraries and produce a matching pairs reports.
+    def top_functions(self, n: int) -> Disassembly:
+        """Returns a new Disassembly composed of the `n` Control Flow Graphs (CFG) with the most instructions.
+
+        Ties are broken by offset, lowest first, making the subset deterministic.
 
         Args:
-            sample_graph (Disassembly) : The Control Flow Graph (CFG) of the malware sample to compare.
-            reference_graphs (list[Disassembly]) : The list of reference Control Flow Graphs (CFG) to compare to.
+            n (int) : Number of Control Flow Graphs (CFG) to keep.
 
         Returns:
-            CompareReport : The function - library matching pairs.
+            Disassembly : Subset of the original disassembly.
         """
 
-    @staticmethod
-    def generate_graphs(sample_list: list[tuple[str, Path]]) -> list[Disassembly]:
-        """Generate the Control Flow Graph (CFG) for each sample.
+    def dedup_graphs(self) -> tuple[Disassembly, int]:
+        """Returns a new Disassembly keeping a single Control Flow Graph (CFG) per unique set of blocks.
+
+        The lowest offset graph of each set is kept, graphs sharing their hash but not their blocks, such
+        as ones differing in blocks shorter than `min_block_instructions`, are both kept.
+
+        Returns:
+            tuple[Disassembly, int] : The deduplicated disassembly and the number of graphs removed.
+        """
+
+    def get_subset(self, ratio: float) -> Disassembly:
+        """Returns a subset of the disassembly corresponding to the supplied ratio.
 
         Args:
-            sample_list (list[tuple[str, Path]]) : The paths to each sample to dissassemble.
+            ratio (float) : Ratio of the disassembly to keep.
 
         Returns:
-            list[Disassembly] : Hashmap of each Control Flow Graph (CFG).
+            Disassembly : Subset of the original disassembly.
         """
 
-class UnsupportedBinaryFormat(Exception):
-    """Raised when an unsupported sample is processed."""
+class CompareConfig:
+    """Settings of the grapher a compare report was produced with."""
 
     @property
-    def message(self) -> str:
-        """Returns the error message of the exception."""
+    def threshold(self) -> float:
+        """Value at which matches were considered significant."""
 
     @property
-    def sample(self) -> str:
-        """Returns the path to the problematic sample."""
+    def mask_immediates(self) -> bool:
+        """Whether immediate and displacement bytes were ignored when comparing instructions."""
+
+    @property
+    def use_call_targets(self) -> bool:
+        """Whether the resolved symbol of a call's target was part of the call instruction's key."""
+
+    @property
+    def cross_arch(self) -> bool:
+        """Whether instructions were compared by their coarse operation class rather than their bytes."""
+
+    @property
+    def min_block_instructions(self) -> int:
+        """Instruction count below which blocks were left out of the graph hashes."""
+
+    @property
+    def strip_nops(self) -> bool:
+        """Whether NOP and padding instructions were left out when hashing and comparing blocks."""
+
+    @property
+    def pooling(self) -> str:
+        """How block similarities were pooled into a function similarity ("mean", "max" or "top_k")."""
+
+    @property
+    def alignment(self) -> str:
+        """How the blocks of two graphs were paired ("greedy" or "hungarian")."""
+
+    @property
+    def size_ratio_gate(self) -> float | None:
+        """Block count ratio below which graph pairs were not compared."""
+
+    @property
+    def graph_norm(self) -> str:
+        """Block count graph similarities were normalized by ("min", "max" or "mean")."""
+
+    @property
+    def block_weighting(self) -> str:
+        """How block similarities were weighted when pooled ("uniform" or "instruction_count")."""
+
+    @property
+    def strict(self) -> bool:
+        """Whether every graph similarity was checked to be finite and within [0, 1]."""
+
+    @property
+    def epsilon(self) -> float:
+        """Tolerance of the similarity comparisons, 0.0 in older reports."""
+
+    @property
+    def report_floor(self) -> float:
+        """Similarity below which method matches were left out of the report, after aggregation."""
+
+    @property
+    def prefix_blocks(self) -> int | None:
+        """Number of leading blocks graph comparisons were limited to."""
+
+    @property
+    def bloom_prefilter(self) -> float | None:
+        """Estimated instruction overlap below which graph pairs were rejected without being compared."""
+
+    @property
+    def max_reference_graphs(self) -> int | None:
+        """Number of leading graphs of each reference that were compared."""
+
+    @property
+    def chunk_size(self) -> int | None:
+        """Number of blocks whose best similarities were computed at once."""
+
+    @property
+    def top_k(self) -> int | None:
+        """Number of best block similarities averaged by the "top_k" pooling."""
+
+    @property
+    def skip_self(self) -> bool:
+        """Whether functions were skipped when matched against themselves."""
+
+    @property
+    def idf_weighted(self) -> bool:
+        """Whether instruction matches were weighted by their rarity."""
+
+    @property
+    def confidence_scale(self) -> float:
+        """The block count `k` of the method match confidence."""
+
+    @property
+    def block_alignment(self) -> bool:
+        """Whether method matches recorded the alignment of their blocks."""
+
+    @property
+    def fuzzy_bytes(self) -> bool:
+        """Whether instructions differing by a single byte were considered equal."""
+
+    @property
+    def fast_block_overlap(self) -> bool:
+        """Whether graphs were compared by the overlap of their block hashes only."""
+
+class CompareReport:
+    """GoGrapher compare report data model."""
+
+    SCHEMA_VERSION: int
+    """Version of the serialized report format, bumped whenever its fields change."""
+
+    @property
+    def schema_version(self) -> int:
+        """The schema version of the report, reports predating the versioning are migrated."""
+
+    @property
+    def sample_name(self) -> str:
+        """The name of the sample this report belongs to."""
+
+    @property
+    def sample_format(self) -> str:
+        """Binary format of the sample (eg: "pe", "elf")."""
+
+    @property
+    def sample_arch(self) -> str:
+        """Architecture of the sample (eg: "amd64", "arm64")."""
+
+    @property
+    def sample_bits(self) -> int:
+        """Bitness of the sample."""
+
+    @property
+    def matches(self) -> list[BinaryMatch]:
+        """Returns the list of matches contained in this report by Go version."""
+
+    @property
+    def config(self) -> CompareConfig:
+        """The settings of the grapher the report was produced with."""
+
+    # TODO: Compute Time
+
+    def set_sample_name(self, name: str) -> None:
+        """Change the name of the sample this report belongs to.
+
+        Args:
+            name (str) : The new name of the sample.
+        """
+
+    def throughput(self) -> float:
+        """Number of references compared per second, 0.0 if no reference or no time was recorded."""
+
+    def avg_time_per_reference(self) -> float:
+        """Average compute time spent per reference in seconds, 0.0 if no reference was compared."""
+
+    def sort_by_similarity(self) -> None:
+        """Sort the binary matches by decreasing similarity, the mean similarity of their methods."""
+
+    def sort_by_coverage(self) -> None:
+        """Sort the binary matches by decreasing coverage, the containment of the reference.
+
+        Ranks candidate libraries by how much of them is embedded in the sample rather than by how
+        close their matches are.
+        """
+
+    def similarity_histogram(self, buckets: int) -> list[int]:
+        """Count the similarity of every method match into equal-width buckets over [0.0, 1.0].
+
+        Args:
+            buckets (int) : Number of buckets of the histogram.
+
+        Returns:
+            list[int] : Number of method matches per bucket.
+        """
+
+    def diff(self, other: CompareReport, epsilon: float = 1e-4) -> ReportDiff:
+        """List the binary matches that appeared, disappeared or changed similarity in another report.
+
+        Args:
+            other (CompareReport) : The new report to compare this one to.
+            epsilon (float) : Similarity difference below which binary matches are considered unchanged.
+
+        Returns:
+            ReportDiff : The differences between both reports.
+        """
+
+    def to_json(self, precision: int | None = 4) -> str:
+        """Returns the JSON representation the the compare report.
+
+        Args:
+            precision (int | None) : Number of decimals the similarity, confidence and containment scores are rounded to,
+                None for full precision. The config is never rounded.
+
+        Returns:
+            str : JSON representation of the report.
+        """
+
+    @staticmethod
+    def from_json(json_data: str) -> CompareReport:
+        """Parse a CompareReport from its JSON representation.
+
+        Reports of an older schema version are migrated to the current one.
+
+        Args:
+            json_data (&str) : The JSON data to parse.
+
+        Returns:
+            CompareReport : The newly parsed instance of CompareReport.
+
+        Raises:
+            InvalidReport : If the data is not a valid report.
+            UnsupportedReportVersion : If the report's schema version is newer than `SCHEMA_VERSION`.
+        """
+
+    def to_json_gz(self, path: Path) -> None:
+        """Write the gzip compressed JSON representation of the compare report to a file.
+
+        Args:
+            path (Path) : Path of the compressed report to write.
+
+        Raises:
+            FileAccessError : If the file could not be written.
+        """
+
+    @staticmethod
+    def iter_jsonl(path: Path) -> Iterator[BinaryMatch]:
+        """Iterate over the BinaryMatch of each line of a JSONL file, such as a checkpoint, one at a time.
+
+        The file is parsed as it is read, processing multi-GB files with bounded memory. Files ending
+        with ".gz" are transparently decompressed. Iteration stops at the first invalid line.
+
+        Args:
+            path (Path) : Path of the JSONL file to read.
+
+        Returns:
+            Iterator[BinaryMatch] : The binary matches, in file order.
+
+        Raises:
+            FileAccessError : If the file could not be read.
+            InvalidReport : If a line is not a valid BinaryMatch.
+        """
+
+    @staticmethod
+    def from_json_gz(path: Path) -> CompareReport:
+        """Parse a CompareReport from a gzip compressed JSON file.
+
+        Args:
+            path (Path) : Path of the compressed report to read.
+
+        Returns:
+            CompareReport : The newly parsed instance of CompareReport.
+
+        Raises:
+            FileAccessError : If the file could not be read.
+            InvalidReport : If the file is not a valid report.
+            UnsupportedReportVersion : If the report's schema version is newer than `SCHEMA_VERSION`.
+        """
+
+    def to_yaml(self) -> str:
+        """Returns the YAML representation the the compare report.
+
+        Returns:
+            str : YAML representation of the report.
+        """
+
+    @staticmethod
+    def from_yaml(yaml_data: str) -> CompareReport:
+        """Parse a CompareReport from its YAML representation.
+
+        Reports of an older schema version are migrated to the current one.
+
+        Args:
+            yaml_data (&str) : The YAML data to parse.
+
+        Returns:
+            CompareReport : The newly parsed instance of CompareReport.
+
+        Raises:
+            InvalidReport : If the data is not a valid report.
+            UnsupportedReportVersion : If the report's schema version is newer than `SCHEMA_VERSION`.
+        """
+
+    def package_summary(self) -> list[PackageMatch]:
+        """Returns the method matches grouped by the Go package of their resolved name.
+
+        Names without a package are left out.
+
+        Returns:
+            list[PackageMatch] : The matches of each package, by decreasing match count.
+        """
+
+    def ambiguous_resolutions(self) -> list[tuple[str, list[int]]]:
+        """Returns the resolved names matched by more than one malware function.
+
+        Often the sign of over-matching at a low threshold, check them before applying a rename script.
+
+        Returns:
+            list[tuple[str, list[int]]] : Each ambiguous name along with the malware offsets it resolves.
+        """
+
+    def extract_matched(self, sample: Disassembly, min_similarity: float = 0.0) -> Disassembly:
+        """Returns a new Disassembly composed of the sample's functions that matched, for a focused re-analysis.
+
+        Args:
+            sample (Disassembly) : The disassembly of the sample the report was produced from.
+            min_similarity (float) : Similarity below which matches are ignored.
+
+        Returns:
+            Disassembly : The matched functions of the sample.
+        """
+
+    def to_ida_script(self, min_similarity: float = 0.0) -> str:
+        """Returns an IDAPython script renaming the sample's functions after their matches.
+
+        A function matched by several references takes the name of its most similar match.
+
+        Args:
+            min_similarity (float) : Similarity below which matches are not applied.
+
+        Returns:
+            str : The IDAPython script.
+        """
+
+    def to_ghidra_script(self, min_similarity: float = 0.0) -> str:
+        """Returns a Ghidra Java script renaming the sample's functions after their matches.
+
+        A function matched by several references takes the name of its most similar match, functions
+        missing from the Ghidra program are created.
+
+        Args:
+            min_similarity (float) : Similarity below which matches are not applied.
+
+        Returns:
+            str : The Ghidra script.
+        """
+
+class SignatureDb:
+    """Portable database of reference disassemblies, avoiding their disassembly on every run."""
+
+    @property
+    def disassemblies(self) -> list[Disassembly]:
+        """The reference disassemblies stored within the database."""
+
+    def __len__(self) -> int:
+        """Returns the number of references stored within the database."""
+
+    @staticmethod
+    def build(disassemblies: list[Disassembly]) -> SignatureDb:
+        """Build a new signature database from a set of reference disassemblies.
+
+        Args:
+            disassemblies (list[Disassembly]) : The reference disassemblies to store.
+
+        Returns:
+            SignatureDb : The newly built signature database.
+        """
+
+    def save(self, path: Path) -> None:
+        """Write the signature database to a file.
+
+        Args:
+            path (Path) : Path of the database to write.
+
+        Raises:
+            FileAccessError : If the file could not be written.
+        """
+
+    @staticmethod
+    def load(path: Path) -> SignatureDb:
+        """Load a signature database from a file.
+
+        Args:
+            path (Path) : Path of the database to read.
+
+        Returns:
+            SignatureDb : The loaded signature database.
+
+        Raises:
+            FileAccessError : If the file could not be read.
+            InvalidSignatureDb : If the file is not a valid signature database.
+        """
+
+class BlockDiff:
+    """Data Model of the instruction differences between two aligned basic blocks."""
+
+    @property
+    def source_offset(self) -> int | None:
+        """Offset of the source block, None if the block only exists in the target method."""
+
+    @property
+    def target_offset(self) -> int | None:
+        """Offset of the target block, None if the block only exists in the source method."""
+
+    @property
+    def similarity(self) -> float:
+        """Normalized similarity ratio between the two blocks."""
+
+    @property
+    def removed(self) -> list[str]:
+        """Instructions of the source block missing from the target block."""
+
+    @property
+    def added(self) -> list[str]:
+        """Instructions of the target block missing from the source block."""
+
+class MethodDiff:
+    """Data Model of the instruction differences between two methods."""
+
+    @property
+    def source_name(self) -> str:
+        """Name of the source method."""
+
+    @property
+    def target_name(self) -> str:
+        """Name of the target method."""
+
+    @property
+    def blocks(self) -> list[BlockDiff]:
+        """The differences of each aligned block pair."""
+
+class BlockAlignment:
+    """Data Model of the one to one alignment of the basic blocks of a sample and a reference method."""
+
+    @property
+    def sample_name(self) -> str:
+        """Name of the sample method."""
+
+    @property
+    def reference_name(self) -> str:
+        """Name of the reference method."""
+
+    @property
+    def matched(self) -> list[tuple[int, int, float]]:
+        """The (sample_offset, reference_offset, similarity) of each aligned block pair, by sample offset."""
+
+    @property
+    def sample_only(self) -> list[int]:
+        """Offsets of the sample blocks without a counterpart in the reference method."""
+
+    @property
+    def reference_only(self) -> list[int]:
+        """Offsets of the reference blocks without a counterpart in the sample method."""
+
+class ReportDiff:
+    """Data Model of the differences between the binary matches of two compare reports."""
+
+    @property
+    def appeared(self) -> list[str]:
+        """References only matched in the new report."""
+
+    @property
+    def disappeared(self) -> list[str]:
+        """References only matched in the old report."""
+
+    @property
+    def changed(self) -> list[tuple[str, float, float]]:
+        """The (reference, old_similarity, new_similarity) of references whose similarity changed."""
+
+    def is_empty(self) -> bool:
+        """Whether both reports hold the same binary matches."""
+
+class Grapher:
+    """Compute a summary of the similarities between a malware sample and a set of clean libraries."""
+
+    def __init__(
+        self,
+        *,
+        threshold: float,
+        display_progress: bool = False,
+        mask_immediates: bool = False,
+        skip_self: bool = False,
+        pooling: str = "mean",
+        top_k: int | None = None,
+        confidence_scale: float = 4.0,
+        block_alignment: bool = False,
+        fuzzy_bytes: bool = False,
+        progress_mode: str | None = None,
+        use_call_targets: bool = False,
+        fast_block_overlap: bool = False,
+        cross_arch: bool = False,
+        alignment: str = "greedy",
+        min_block_instructions: int = 0,
+        size_ratio_gate: float | None = None,
+        checkpoint_path: Path | None = None,
+        graph_norm: str = "min",
+        strict: bool = False,
+        epsilon: float = 1e-6,
+        progress_style: str = "default",
+        report_floor: float = 0.0,
+        strip_nops: bool = False,
+        prefix_blocks: int | None = None,
+        bloom_prefilter: float | None = None,
+        max_reference_graphs: int | None = None,
+        chunk_size: int | None = None,
+        block_weighting: str = "uniform",
+    ) -> None:
+        """Initialize a new GoGrapher instance.
+
+        Args:
+            threshold (f32) : Value at which matches are considered significant, within [0, 1].
+            display_progress (bool): Weather to output progress updates to the console.
+            mask_immediates (bool): Weather to ignore immediate and displacement bytes when comparing instructions.
+            skip_self (bool): Weather to skip matching a function against itself when comparing a binary to itself.
+            pooling (str): How block similarities are pooled into a function similarity ("mean", "max" or "top_k").
+            top_k (int | None): Number of best block similarities averaged by the "top_k" pooling.
+            confidence_scale (float): Block count at which a match confidence reaches ~63% of its similarity.
+            block_alignment (bool): Weather method matches record the alignment of their blocks.
+            fuzzy_bytes (bool): Weather instructions differing by a single byte are considered equal, slower. Call targets must
+                still be equal, disabled by `cross_arch`.
+            progress_mode (str | None): How progress is reported ("none", "bar" or "log"), overrides `display_progress`.
+            use_call_targets (bool): Weather the resolved symbol of a call's target is part of the call instruction's key.
+            fast_block_overlap (bool): Weather graphs are compared by the overlap of their block hashes only, much faster. Scores
+                are lower than the precise ones, by 0.3 on average, lower `threshold` accordingly.
+            cross_arch (bool): Weather instructions are compared by their coarse operation class, approximate but architecture independent.
+            alignment (str): How blocks are paired ("greedy" or "hungarian"), "hungarian" is one to one but adds an O(n^3) assignment per graph pair.
+            min_block_instructions (int): Instruction count below which blocks are left out of the graph hashes, changing hash values and fast-path hits.
+            size_ratio_gate (float | None): Block count ratio below which graph pairs are not compared, trading recall for speed.
+            checkpoint_path (Path | None): JSONL file completed references are checkpointed to, `compare` resuming from it when run again.
+            graph_norm (str): Block count graph similarities are normalized by ("min", "max" or "mean"), "max" penalizing size differences.
+            strict (bool): Weather comparisons raise InvalidSimilarity on a non finite similarity or one outside of [0, 1], for debugging.
+            epsilon (float): Tolerance of the similarity comparisons, a similarity within it of 1.0 is an exact match and ties within it keep the first match.
+            progress_style (str): Style of the progress bars ("default" or "ascii"), "ascii" avoiding unicode and colors for consoles rendering them poorly.
+            report_floor (float): Similarity below which method matches are left out of the report, binary similarities still accounting for them.
+            strip_nops (bool): Weather NOP and padding instructions are left out when hashing and comparing blocks, changing hash values.
+            prefix_blocks (int | None): Number of leading blocks graph comparisons are limited to, a fast prefilter missing functions whose entry changed.
+            bloom_prefilter (float | None): Estimated instruction overlap, from per-graph Bloom filters, below which graph pairs are rejected without being compared.
+            max_reference_graphs (int | None): Number of leading graphs of each reference that are compared, for smoke tests.
+            chunk_size (int | None): Number of reference blocks whose best similarities are computed at once, bounding the retained similarities of huge functions with the max and top_k poolings.
+            block_weighting (str): How block similarities are weighted when pooled ("uniform" or "instruction_count"), "instruction_count" letting large blocks dominate.
+
+        Returns:
+            GoGrapher : The newly initialized GoGrapher instance.
+
+        Raises:
+            InvalidOption : If `confidence_scale`, `size_ratio_gate`, `epsilon`, `report_floor` or `bloom_prefilter` is out of range.
+            ValueError : If another option is invalid.
+        """
+
+    def compare(
+        self, sample_graph: Disassembly, reference_graphs: list[Disassembly], *, name: str | None = None
+    ) -> CompareReport:
+        """Compare a malware sample to a clean set of libraries and produce a matching pairs reports.
+
+        Args:
+            sample_graph (Disassembly) : The Control Flow Graph (CFG) of the malware sample to compare.
+            reference_graphs (list[Disassembly]) : The list of reference Control Flow Graphs (CFG) to compare to.
+            name (str | None) : Title of the report, defaults to the name of the sample.
+
+        Returns:
+            CompareReport : The function - library matching pairs.
+
+        Raises:
+            InvalidSimilarity : In strict mode, if a graph similarity is invalid.
+        """
+
+    def compare_streaming(
+        self, sample_graph: Disassembly, reference_graphs: list[Disassembly], callback: Callable[[BinaryMatch], object]
+    ) -> None:
+        """Compare a malware sample to a clean set of libraries, streaming the result of each reference.
+
+        Args:
+            sample_graph (Disassembly) : The Control Flow Graph (CFG) of the malware sample to compare.
+            reference_graphs (list[Disassembly]) : The list of reference Control Flow Graphs (CFG) to compare to.
+            callback (Callable[[BinaryMatch], object]) : Called with the matches of each reference as it completes.
+
+        Raises:
+            InvalidSimilarity : In strict mode, once every reference was compared, if a graph similarity is invalid.
+        """
+
+    def compare_lazy(
+        self,
+        sample_graph: Disassembly,
+        reference_paths: list[Path],
+        *,
+        batch_size: int = 1,
+        name: str | None = None,
+    ) -> CompareReport:
+        """Compare a malware sample to references disassembled lazily, `batch_size` at a time, bounding memory use.
+
+        Args:
+            sample_graph (Disassembly) : The Control Flow Graph (CFG) of the malware sample to compare.
+            reference_paths (list[Path]) : Paths to the references to compare to, named after their file names.
+            batch_size (int) : Number of references disassembled and compared in parallel, trading memory for speed.
+            name (str | None) : Title of the report, defaults to the name of the sample.
+
+        Returns:
+            CompareReport : The function - library matching pairs.
+
+        Raises:
+            UnsupportedBinaryFormat : If one of the references could not be disassembled.
+        """
+
+    def compare_to_db(self, sample_graph: Disassembly, db: SignatureDb, *, name: str | None = None) -> CompareReport:
+        """Compare a malware sample to the references of a prebuilt signature database.
+
+        Args:
+            sample_graph (Disassembly) : The Control Flow Graph (CFG) of the malware sample to compare.
+            db (SignatureDb) : The signature database holding the references to compare to.
+            name (str | None) : Title of the report, defaults to the name of the sample.
+
+        Returns:
+            CompareReport : The function - library matching pairs.
+
+        Raises:
+            InvalidSimilarity : In strict mode, if a graph similarity is invalid.
+        """
+
+    def config(self) -> CompareConfig:
+        """The settings of the grapher, as recorded in the reports it produces."""
+
+    def cancel(self) -> None:
+        """Cancel the comparisons running on this grapher, from any thread.
+
+        The running comparisons return early with partial results, only holding the references
+        that were entirely compared. The grapher stays cancelled until `reset_cancel` is called.
+        A KeyboardInterrupt raised while the grapher is running cancels it as well.
+        """
+
+    def is_cancelled(self) -> bool:
+        """Whether the grapher was cancelled, in which case comparison results may be partial."""
+
+    def reset_cancel(self) -> None:
+        """Clear the cancellation flag so that the grapher can be used again."""
+
+    def fit_idf(self, reference_graphs: list[Disassembly]) -> None:
+        """Weight instruction matches by their rarity across the reference corpus.
+
+        Common instructions then weigh less than rare distinctive ones, scores are only comparable
+        between runs fitted on the same references.
+
+        Args:
+            reference_graphs (list[Disassembly]) : The reference corpus to compute the instruction frequencies from.
+        """
+
+    def clear_idf(self) -> None:
+        """Stop weighting instruction matches by their rarity."""
+
+    def compare_graph_lists(
+        self,
+        sample_graphs: list[ControlFlowGraph],
+        reference_graphs: list[ControlFlowGraph],
+        names: tuple[str, str],
+        *,
+        is_self: bool = False,
+    ) -> BinaryMatch:
+        """Compare two lists of Control Flow Graphs (CFG) and produce their matching pairs.
+
+        Args:
+            sample_graphs (list[ControlFlowGraph]) : The Control Flow Graphs (CFG) of the sample.
+            reference_graphs (list[ControlFlowGraph]) : The Control Flow Graphs (CFG) of the reference.
+            names (tuple[str, str]) : The names of the sample and of the reference.
+            is_self (bool) : Whether both lists come from the same binary, in which case a function is never matched against itself if `skip_self` is enabled.
+
+        Returns:
+            BinaryMatch : The function matching pairs.
+
+        Raises:
+            InvalidSimilarity : In strict mode, if a graph similarity is invalid.
+        """
+
+    def compare_many(self, sample_graphs: list[Disassembly], reference_graphs: list[Disassembly]) -> list[CompareReport]:
+        """Compare several malware samples to the same clean set of libraries.
+
+        Args:
+            sample_graphs (list[Disassembly]) : The Control Flow Graphs (CFG) of the malware samples to compare.
+            reference_graphs (list[Disassembly]) : The list of reference Control Flow Graphs (CFG) to compare to.
+
+        Returns:
+            list[CompareReport] : The function - library matching pairs of each sample, in order.
+
+        Raises:
+            InvalidSimilarity : In strict mode, if a graph similarity is invalid.
+        """
+
+    def compare_report_into(
+        self, sample_graph: Disassembly, reference_graphs: list[Disassembly], report: CompareReport
+    ) -> None:
+        """Compare a malware sample to a clean set of libraries and append the results to an existing report.
+
+        Args:
+            sample_graph (Disassembly) : The Control Flow Graph (CFG) of the malware sample to compare.
+            reference_graphs (list[Disassembly]) : The list of reference Control Flow Graphs (CFG) to compare to.
+            report (CompareReport) : The report of the same sample to append the results to.
+
+        Raises:
+            SampleNameMismatch : If the report belongs to a different sample.
+        """
+
+    def graph_similarity(self, lhs: ControlFlowGraph, rhs: ControlFlowGraph) -> float:
+        """Compute the similarity of two methods, as used to score method matches.
+
+        The threshold is not applied.
+
+        Args:
+            lhs (ControlFlowGraph) : The Control Flow Graph (CFG) of the first method.
+            rhs (ControlFlowGraph) : The Control Flow Graph (CFG) of the second method.
+
+        Returns:
+            float : The similarity of both methods, between 0 and 1.
+
+        Raises:
+            InvalidSimilarity : In strict mode, if the similarity is invalid.
+        """
+
+    def block_similarity_matrix(self, lhs: ControlFlowGraph, rhs: ControlFlowGraph) -> list[list[float]]:
+        """Compute the similarity of every pair of blocks of two methods, as pooled into their similarity.
+
+        Args:
+            lhs (ControlFlowGraph) : The Control Flow Graph (CFG) of the first method, its blocks being the rows.
+            rhs (ControlFlowGraph) : The Control Flow Graph (CFG) of the second method, its blocks being the columns.
+
+        Returns:
+            list[list[float]] : The similarity of each pair of blocks, between 0 and 1.
+        """
+
+    def score_pairs(
+        self, sample: Disassembly, reference: Disassembly, pairs: list[tuple[int, int]]
+    ) -> list[tuple[int, int, float]]:
+        """Score specific function pairs, such as ground truth matches.
+
+        The threshold is not applied.
+
+        Args:
+            sample (Disassembly) : The disassembly of the sample.
+            reference (Disassembly) : The disassembly of the reference.
+            pairs (list[tuple[int, int]]) : The (sample_offset, reference_offset) pairs to score.
+
+        Returns:
+            list[tuple[int, int, float]] : The (sample_offset, reference_offset, similarity) of each pair.
+
+        Raises:
+            FunctionNotFound : If an offset has no function in its disassembly.
+            InvalidSimilarity : In strict mode, if a graph similarity is invalid.
+        """
+
+    def diff_methods(self, source: ControlFlowGraph, target: ControlFlowGraph) -> MethodDiff:
+        """Compute the instruction level differences between two methods.
+
+        Args:
+            source (ControlFlowGraph) : The Control Flow Graph (CFG) of the first method.
+            target (ControlFlowGraph) : The Control Flow Graph (CFG) of the second method.
+
+        Returns:
+            MethodDiff : The added and removed instructions of each aligned block pair.
+        """
+
+    def analyze(self, sample_path: Path, reference_paths: list[tuple[str, Path]]) -> CompareReport:
+        """Disassemble a malware sample and a set of references, then compare them in one operation.
+
+        Args:
+            sample_path (Path) : Path to the malware sample to analyze, named after its file name.
+            reference_paths (list[tuple[str, Path]]) : The version and path of each reference to compare to.
+
+        Returns:
+            CompareReport : The function - library matching pairs.
+
+        Raises:
+            UnsupportedBinaryFormat : If one of the binaries could not be disassembled.
+            SampleNotFound : If the sample could not be disassembled.
+        """
+
+    def analyze_many(
+        self, sample_paths: list[Path], reference_paths: list[tuple[str, Path]]
+    ) -> list[CompareReport]:
+        """Disassemble several malware samples and a set of references, then compare each sample to the references.
+
+        Args:
+            sample_paths (list[Path]) : Paths to the malware samples to analyze, named after their file names.
+            reference_paths (list[tuple[str, Path]]) : The version and path of each reference to compare to.
+
+        Returns:
+            list[CompareReport] : One report per sample, in the same order.
+
+        Raises:
+            UnsupportedBinaryFormat : If one of the binaries could not be disassembled.
+            SampleNotFound : If a sample could not be disassembled.
+        """
+
+    @staticmethod
+    def generate_graphs(sample_list: list[tuple[str, Path]]) -> list[Disassembly]:
+        """Generate the Control Flow Graph (CFG) for each sample.
+
+        Args:
+            sample_list (list[tuple[str, Path]]) : The paths to each sample to dissassemble.
+
+        Returns:
+            list[Disassembly] : Hashmap of each Control Flow Graph (CFG).
+        """
+
+    def generate_graphs_lenient(
+        self, sample_list: list[tuple[str, Path]]
+    ) -> tuple[list[Disassembly], list[tuple[Path, Exception]]]:
+        """Generate the Control Flow Graph (CFG) for each sample, carrying on past failing samples.
+
+        Args:
+            sample_list (list[tuple[str, Path]]) : The paths to each sample to dissassemble.
+
+        Returns:
+            tuple[list[Disassembly], list[tuple[Path, Exception]]] : The successful disassemblies and the failures.
+        """
+
+class Logging:
+    """Routes the diagnostics of GoGrapher into Python's `logging` module."""
+
+    @staticmethod
+    def init(level: str = "debug") -> None:
+        """Forward the diagnostics of GoGrapher at or above a level to Python's `logging` module.
+
+        Records are logged to the loggers named after their module (eg: "gographer.grapher"), trace
+        records use the level 5. May be called again to change the level.
+
+        Args:
+            level (str) : Minimum level forwarded ("off", "error", "warning", "info", "debug" or "trace").
+        """
+
+
+class GoGrapherError(Exception):
+    """Base class of GoGrapher's errors."""
+
+    @property
+    def message(self) -> str:
+        """Returns the error message of the exception."""
+
+class UnsupportedBinaryFormat(GoGrapherError):
+    """Raised when an unsupported sample is processed."""
+
+    @property
+    def sample(self) -> str:
+        """Returns the path to the problematic sample."""
+
+class DisassemblyPanicked(GoGrapherError):
+    """Raised when the disassembler crashed while processing a sample."""
+
+    @property
+    def sample(self) -> str:
+        """Returns the path to the problematic sample."""
+
+class DisassemblyFailed(GoGrapherError):
+    """Raised when the disassembler failed to process a sample, such as a malformed one."""
+
+    @property
+    def sample(self) -> str:
+        """Returns the path to the problematic sample."""
+
+    @property
+    def reason(self) -> str:
+        """Returns the reason the sample could not be disassembled."""
+
+class SampleNotFound(GoGrapherError):
+    """Raised when the sample could not be disassembled, so no comparison was possible."""
+
+    @property
+    def sample(self) -> str:
+        """Returns the path to the problematic sample."""
+
+class SampleNameMismatch(GoGrapherError):
+    """Raised when results are appended to a report of a different sample."""
+
+    @property
+    def expected(self) -> str:
+        """Returns the sample name of the report."""
+
+    @property
+    def found(self) -> str:
+        """Returns the sample name of the appended results."""
+
+class FileAccessError(GoGrapherError):
+    """Raised when a file could not be read or written."""
+
+    @property
+    def path(self) -> str:
+        """Returns the path to the problematic file."""
+
+    @property
+    def reason(self) -> str:
+        """Returns the reason the file could not be accessed."""
+
+class InvalidSignatureDb(GoGrapherError):
+    """Raised when a signature database file could not be parsed."""
+
+    @property
+    def path(self) -> str:
+        """Returns the path to the problematic file."""
+
+    @property
+    def reason(self) -> str:
+        """Returns the reason the database is invalid."""
+
+class InvalidGlob(GoGrapherError):
+    """Raised when a glob pattern is invalid or matches no file."""
+
+    @property
+    def pattern(self) -> str:
+        """Returns the problematic glob pattern."""
+
+    @property
+    def reason(self) -> str:
+        """Returns the reason the pattern is invalid."""
+
+class InvalidRegex(GoGrapherError):
+    """Raised when a regex expression is invalid."""
+
+    @property
+    def pattern(self) -> str:
+        """Returns the problematic regex expression."""
+
+    @property
+    def reason(self) -> str:
+        """Returns the reason the expression is invalid."""
+
+class UnsupportedReportVersion(GoGrapherError):
+    """Raised when a report was written with a newer schema version than supported."""
+
+    @property
+    def found(self) -> int:
+        """Returns the schema version of the report."""
+
+    @property
+    def supported(self) -> int:
+        """Returns the newest supported schema version."""
+
+class FunctionNotFound(GoGrapherError):
+    """Raised when no function exists at the requested offset of a disassembly."""
+
+    @property
+    def sample(self) -> str:
+        """Returns the name of the disassembly missing the function."""
+
+    @property
+    def offset(self) -> int:
+        """Returns the offset without a function."""
+
+class InvalidSimilarity(GoGrapherError):
+    """Raised in strict mode when a graph similarity is not finite or outside of [0, 1]."""
+
+    @property
+    def reference(self) -> str:
+        """Returns the name of the reference function."""
+
+    @property
+    def sample(self) -> str:
+        """Returns the name of the sample function."""
+
+    @property
+    def similarity(self) -> float:
+        """Returns the invalid similarity."""
+
+class InvalidReport(GoGrapherError):
+    """Raised when a report file could not be parsed."""
+
+    @property
+    def path(self) -> str:
+        """Returns the path to the problematic file."""
+
+    @property
+    def reason(self) -> str:
+        """Returns the reason the report is invalid."""
+
+class InvalidOption(GoGrapherError):
+    """Raised when a Grapher option is given an invalid value."""
+
+    @property
+    def option(self) -> str:
+        """Returns the name of the invalid option."""
+
+    @property
+    def reason(self) -> str:
+        """Returns the reason the value is invalid."""
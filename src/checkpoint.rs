@@ -0,0 +1,129 @@
+use std::{
+    collections::HashMap,
+    fs::{File, OpenOptions},
+    io::{BufRead, BufReader, Write},
+    path::Path,
+    sync::Mutex,
+};
+
+use crate::error::Error;
+use crate::r#match::Binary as BinaryMatch;
+
+/// JSONL checkpoint of the references already compared to a sample.
+///
+/// Each line holds the `BinaryMatch` of a completed reference, appended as soon as it completes so
+/// that an interrupted comparison can resume where it stopped.
+pub(crate) struct Checkpoint {
+    file: Mutex<File>,
+    completed: HashMap<String, BinaryMatch>,
+}
+
+impl Checkpoint {
+    /// Open the checkpoint at `path`, creating it if needed, and load the matches of `sample_name`.
+    ///
+    /// Lines that can not be parsed are skipped. A last line truncated by a crash, which lacks its
+    /// newline, is removed so that the next record starts on a line of its own.
+    pub(crate) fn open(path: &Path, sample_name: &str) -> Result<Self, Error> {
+        let file_error = |error: std::io::Error| Error::FileAccess {
+            path: path.to_string_lossy().to_string(),
+            reason: error.to_string(),
+        };
+
+        let mut completed: HashMap<String, BinaryMatch> = HashMap::new();
+        // Length of the checkpoint up to its last complete line.
+        let mut complete_len: u64 = 0;
+        if path.exists() {
+            let mut reader: BufReader<File> = BufReader::new(File::open(path).map_err(file_error)?);
+            let mut line: Vec<u8> = Vec::new();
+            loop {
+                line.clear();
+                if reader.read_until(b'\n', &mut line).map_err(file_error)? == 0 {
+                    break;
+                }
+                if line.last() != Some(&b'\n') {
+                    tracing::warn!("removed the torn last checkpoint line");
+                    break;
+                }
+                complete_len += line.len() as u64;
+                match serde_json::from_slice::<BinaryMatch>(&line) {
+                    Ok(binary) if binary.source() == sample_name => {
+                        // Checkpoints written before method matches recorded their reference.
+                        let binary: BinaryMatch = binary.with_method_references();
+                        completed.insert(binary.dest().clone(), binary);
+                    }
+                    Ok(_) => {}
+                    Err(error) => tracing::warn!(%error, "skipped invalid checkpoint line"),
+                }
+            }
+        }
+
+        let file: File = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(file_error)?;
+        if file.metadata().map_err(file_error)?.len() > complete_len {
+            file.set_len(complete_len).map_err(file_error)?;
+        }
+        tracing::debug!(path = %path.display(), completed = completed.len(), "checkpoint opened");
+
+        Ok(Self {
+            file: Mutex::new(file),
+            completed,
+        })
+    }
+
+    /// The checkpointed match of the reference named `reference_name`, if it was completed.
+    pub(crate) fn completed(&self, reference_name: &str) -> Option<&BinaryMatch> {
+        self.completed.get(reference_name)
+    }
+
+    /// Append the match of a completed reference to the checkpoint.
+    pub(crate) fn record(&self, binary: &BinaryMatch) {
+        let mut line: String = serde_json::to_string(binary).expect("Failed to serialize");
+        line.push('\n');
+
+        // A single write per line, so that a crash can at worst truncate the last line.
+        let mut file = self.file.lock().expect("Unexpected error while writing the checkpoint");
+        if let Err(error) = file.write_all(line.as_bytes()).and_then(|_| file.flush()) {
+            tracing::warn!(%error, reference = %binary.dest(), "failed to checkpoint reference");
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::r#match::Method as MethodMatch;
+    use crate::test_utils::{chain_graph, keyed_block};
+
+    // The match of a single function of `sample_name` against the reference `reference_name`.
+    fn binary(sample_name: &str, reference_name: &str) -> BinaryMatch {
+        let graph = chain_graph("main.f", 0x1000, vec![keyed_block(0x1000, &["55", "c3"])]);
+        BinaryMatch::new(sample_name, reference_name, &[MethodMatch::new(&graph, &graph, 1.0, 4.0)], 1, 1)
+    }
+
+    #[test]
+    fn open_removes_torn_last_line() {
+        let path = std::env::temp_dir().join(format!("gographer-checkpoint-{}.jsonl", std::process::id()));
+        let torn: String = serde_json::to_string(&binary("sample", "torn")).unwrap();
+        let contents: String = format!(
+            "{}\n{}",
+            serde_json::to_string(&binary("sample", "first")).unwrap(),
+            &torn[..torn.len() / 2],
+        );
+        std::fs::write(&path, contents).unwrap();
+
+        let checkpoint: Checkpoint = Checkpoint::open(&path, "sample").unwrap();
+        assert!(checkpoint.completed("first").is_some());
+        checkpoint.record(&binary("sample", "second"));
+        drop(checkpoint);
+
+        // The record following the torn line is read back rather than joined to it.
+        let checkpoint: Checkpoint = Checkpoint::open(&path, "sample").unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(checkpoint.completed("first").is_some());
+        assert!(checkpoint.completed("second").is_some());
+        assert!(checkpoint.completed("torn").is_none());
+    }
+}
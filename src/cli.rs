@@ -1,32 +1,139 @@
-use std::{fs::File, io::Write, path::PathBuf};
+use std::{collections::HashMap, fs::File, io::Write, path::{Path, PathBuf}, process::ExitCode};
 
-use clap::Parser;
+use clap::{Parser, ValueEnum};
 use colored_json::ToColoredJson;
 use pyo3::{pyclass, pymethods, Python};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crate::compare_report::CompareReport;
-use crate::disassembly::Disassembly;
 use crate::error::Error;
 use crate::grapher::Grapher;
 
 
+/// Order of the references within the report.
+#[derive(Clone, Copy, ValueEnum)]
+pub enum SortKey {
+    // Mean similarity of the matched methods, how close the matches are.
+    Similarity,
+    // Fraction of the reference's methods matched, how much of the reference is present.
+    Coverage,
+}
+
 #[derive(Parser)]
 pub struct Args {
-    /// Path to the GO sample to analyze.
+    /// Path to the GO sample to analyze, a glob pattern with "--output-dir" to analyze several.
     pub sample_path: PathBuf,
 
-    /// Path to the GO reference samples to compare to.
+    /// Path to the GO reference samples to compare to, glob patterns are expanded.
     pub reference_path: Vec<PathBuf>,
 
-    /// Path of the output JSON report.
+    /// Path of the output JSON report, gzip compressed if it ends with ".gz".
     #[arg(short = 'o', long = "output")]
     pub output_path: Option<PathBuf>,
 
-    /// Value at which matches are considered significant.
-    #[arg(short = 't', long = "threshold", default_value = "0.0")]
-    pub threshold: f32,
+    /// Directory to write one "<SAMPLE_NAME>.json" report per sample to, created if needed.
+    #[arg(short = 'd', long = "output-dir", conflicts_with = "output_path")]
+    pub output_dir: Option<PathBuf>,
+
+    /// Value at which method matches are considered significant.
+    #[arg(
+        short = 't',
+        long = "method-threshold",
+        visible_alias = "threshold",
+        default_value = "0.0",
+        value_parser = parse_ratio
+    )]
+    pub method_threshold: f32,
+
+    /// Similarity below which references are left out of the report.
+    #[arg(short = 'b', long = "min-binary-similarity", value_parser = parse_ratio)]
+    pub min_binary_similarity: Option<f32>,
+
+    /// Order of the references, by similarity of their matches or by coverage of their methods.
+    #[arg(short = 's', long = "sort-by", value_enum, default_value = "similarity")]
+    pub sort_by: SortKey,
+
+    /// File listing additional reference samples, one path or "version<TAB>path" per line.
+    #[arg(short = 'm', long = "manifest")]
+    pub manifest_path: Option<PathBuf>,
+
+    /// Wall-clock limit of the run in seconds, past which the comparison is cancelled and a partial
+    /// report holding the references compared in time is written, exiting with code 124.
+    #[arg(long = "timeout", value_name = "SECONDS")]
+    pub timeout: Option<u64>,
+}
+
+/// Parse a ratio argument, which must lie within `[0, 1]`.
+fn parse_ratio(value: &str) -> Result<f32, String> {
+    let ratio: f32 = value.parse().map_err(|_| format!("{value:?} is not a number"))?;
+    if !(0.0..=1.0).contains(&ratio) {
+        return Err(format!(
+            "{ratio} is not within [0, 1], similarities range from 0 (unrelated) to 1 (identical)"
+        ));
+    }
+    Ok(ratio)
+}
+
+/// Read a manifest of reference samples.
+///
+/// Each line is either a path, versioned by its file name, or a `version<TAB>path` pair. Blank
+/// lines and lines starting with `#` are ignored.
+fn read_manifest(manifest_path: &Path) -> Result<Vec<(String, PathBuf)>, Error> {
+    let manifest: String = std::fs::read_to_string(manifest_path).map_err(|error| Error::FileAccess {
+        path: manifest_path.to_string_lossy().to_string(),
+        reason: error.to_string(),
+    })?;
+
+    Ok(manifest
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .map(|line| match line.split_once('\t') {
+            Some((version, path)) => (version.trim().to_string(), PathBuf::from(path.trim())),
+            None => {
+                let path: PathBuf = PathBuf::from(line);
+                let filename: String = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_else(|| line.to_string());
+                (filename, path)
+            }
+        })
+        .collect())
+}
+
+/// Expand the reference paths containing `*` or `?` wildcards to the files they match.
+///
+/// Explicit paths are passed through unchanged, patterns matching no file are reported as errors
+/// rather than silently ignored.
+fn expand_reference_paths(reference_paths: &[PathBuf]) -> Result<Vec<PathBuf>, Error> {
+    let mut expanded: Vec<PathBuf> = Vec::with_capacity(reference_paths.len());
+
+    for path in reference_paths {
+        let pattern: String = path.to_string_lossy().to_string();
+        if !pattern.contains(['*', '?']) {
+            expanded.push(path.clone());
+            continue;
+        }
+
+        let invalid_glob = |reason: String| Error::InvalidGlob {
+            pattern: pattern.clone(),
+            reason,
+        };
+        let mut matches: Vec<PathBuf> = glob::glob(&pattern)
+            .map_err(|error| invalid_glob(error.to_string()))?
+            .filter_map(Result::ok)
+            .filter(|path| path.is_file())
+            .collect();
+        if matches.is_empty() {
+            return Err(invalid_glob("no file matched".to_string()));
+        }
+        matches.sort();
+        expanded.extend(matches);
+    }
+
+    Ok(expanded)
 }
 
 /// Implements the comand line interface of GoGrapher.
@@ -34,17 +141,29 @@ pub struct Args {
 pub struct Cli;
 
 impl Cli {
+    /// Exit code of runs cancelled by the "--timeout" option, following the `timeout` utility.
+    pub const TIMEOUT_EXIT_CODE: u8 = 124;
+
     /// Parse the cli arguments and execute the requested commands.
-    pub fn run_cli() {
-        Cli::parse_cli(&std::env::args().collect::<Vec<String>>());
+    pub fn run_cli() -> ExitCode {
+        ExitCode::from(Cli::parse_cli(&std::env::args().collect::<Vec<String>>()))
     }
 
-    fn parse_cli(args: &[String]) {
+    // Returns the exit code of the run.
+    fn parse_cli(args: &[String]) -> u8 {
         // Implements the comand line interface of GoGrapher.
         let args = Args::parse_from(args);
-        let grapher: Grapher = Grapher::new(args.threshold, true);
+        let grapher: Grapher = Grapher::new(args.method_threshold, true);
+
+        let reference_path: Vec<PathBuf> = match expand_reference_paths(&args.reference_path) {
+            Ok(reference_path) => reference_path,
+            Err(error) => {
+                println!("{error}");
+                return 0;
+            }
+        };
 
-        let mut reference_paths: Vec<(String, PathBuf)> = args.reference_path.iter().map(|path|{
+        let mut reference_paths: Vec<(String, PathBuf)> = reference_path.iter().map(|path|{
             let filename: String = path.file_name()
                 .expect("Reference path missing filename")
                 .to_str()
@@ -53,29 +172,43 @@ impl Cli {
             (filename, path.clone())
         }).collect();
 
-        let sample_filename: String = args.sample_path.file_name()
-            .expect("Sample path missing filename")
-            .to_str()
-            .expect("Couldn't convert filename")
-            .to_string();
-        reference_paths.push((sample_filename, args.sample_path.clone()));
+        if let Some(manifest_path) = &args.manifest_path {
+            match read_manifest(manifest_path) {
+                Ok(manifest_paths) => reference_paths.extend(manifest_paths),
+                Err(error) => {
+                    println!("{error}");
+                    return 0;
+                }
+            }
+        }
 
-        // Disassemble the necessary samples.
-        let sample_graph_result: Result<Vec<Disassembly>, Error> = grapher.generate_graphs(&reference_paths);
-        match sample_graph_result {
+        if let Some(output_dir) = &args.output_dir {
+            let (result, timed_out) = Cli::with_timeout(&grapher, args.timeout, || {
+                Cli::analyze_to_dir(&grapher, &args, &reference_paths, output_dir)
+            });
+            if let Err(error) = result {
+                println!("{error}");
+            }
+            return Cli::exit_code(args.timeout, timed_out);
+        }
+
+        // Disassemble the necessary samples and compare them.
+        let (result, timed_out) = Cli::with_timeout(&grapher, args.timeout, || {
+            grapher.analyze(&args.sample_path, &reference_paths)
+        });
+        match result {
             Err(error) => println!("{error}"),
-            Ok(mut samples_graph) => {
-                let sample_index: usize = samples_graph
-                    .iter()
-                    .position(|disassembly| disassembly.path == args.sample_path)
-                    .expect("Missing sample disassembly");
-                let malware_graph: Disassembly = samples_graph.swap_remove(sample_index);
-
-                let report: CompareReport = grapher.compare(malware_graph, samples_graph);
+            Ok(mut report) => {
+                Cli::filter_report(&args, &mut report);
                 let report_json: String = report.to_json();
 
                 if let Some(path) = args.output_path {
-                    if let Ok(mut out_file) = File::create(path) {
+                    // Transparently compress reports with a gzip extension.
+                    if path.extension().is_some_and(|extension| extension == "gz") {
+                        if let Err(error) = report.to_json_gz(&path) {
+                            println!("{error}");
+                        }
+                    } else if let Ok(mut out_file) = File::create(path) {
                         out_file.write_all(report_json.as_bytes()).expect("Couldn't write report file");
                     }
                 } else {
@@ -85,6 +218,96 @@ impl Cli {
             }
         }
 
+        Cli::exit_code(args.timeout, timed_out)
+    }
+
+    // Run `task` on a separate thread, cancelling the grapher once `timeout` seconds have elapsed.
+    //
+    // Returns the task's result along with whether it timed out. The cancelled comparisons bail
+    // out early with partial results, disassembly is not interrupted.
+    fn with_timeout<T: Send>(grapher: &Grapher, timeout: Option<u64>, task: impl FnOnce() -> T + Send) -> (T, bool) {
+        let Some(timeout) = timeout.map(Duration::from_secs) else {
+            return (task(), false);
+        };
+
+        let start: Instant = Instant::now();
+        thread::scope(|scope| {
+            let thread_handle: thread::ScopedJoinHandle<T> = scope.spawn(task);
+            let mut timed_out: bool = false;
+
+            while !thread_handle.is_finished() {
+                if !timed_out && start.elapsed() >= timeout {
+                    grapher.cancel();
+                    timed_out = true;
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+
+            (thread_handle.join().expect("Analysis thread panicked"), timed_out)
+        })
+    }
+
+    // The exit code of the run, reporting on stderr whether it timed out.
+    fn exit_code(timeout: Option<u64>, timed_out: bool) -> u8 {
+        if !timed_out {
+            return 0;
+        }
+        eprintln!(
+            "WARNING: Run timed out after {}s, the report only holds the references compared in time !",
+            timeout.unwrap_or_default()
+        );
+        Cli::TIMEOUT_EXIT_CODE
+    }
+
+    // Apply the reference filtering and ordering options to a report.
+    fn filter_report(args: &Args, report: &mut CompareReport) {
+        if let Some(min_similarity) = args.min_binary_similarity {
+            report.retain_binaries(min_similarity);
+        }
+        match args.sort_by {
+            SortKey::Similarity => report.sort_by_similarity(),
+            SortKey::Coverage => report.sort_by_coverage(),
+        }
+    }
+
+    // Analyze each sample matched by the sample path and write its report to the output directory.
+    fn analyze_to_dir(
+        grapher: &Grapher,
+        args: &Args,
+        reference_paths: &[(String, PathBuf)],
+        output_dir: &Path,
+    ) -> Result<(), Error> {
+        let file_error = |path: &Path, error: std::io::Error| Error::FileAccess {
+            path: path.to_string_lossy().to_string(),
+            reason: error.to_string(),
+        };
+
+        let sample_paths: Vec<PathBuf> = expand_reference_paths(std::slice::from_ref(&args.sample_path))?;
+        // Reports are named after the file name of their sample, fail before the analysis rather than
+        // letting samples from different directories overwrite each other's report.
+        let mut sample_names: HashMap<String, &PathBuf> = HashMap::new();
+        for sample_path in &sample_paths {
+            let sample_name: String = sample_path
+                .file_name()
+                .unwrap_or(sample_path.as_os_str())
+                .to_string_lossy()
+                .to_string();
+            if let Some(other_path) = sample_names.insert(sample_name.clone(), sample_path) {
+                return Err(Error::FileAccess {
+                    path: output_dir.join(format!("{sample_name}.json")).to_string_lossy().to_string(),
+                    reason: format!("samples {other_path:?} and {sample_path:?} would share the report"),
+                });
+            }
+        }
+        std::fs::create_dir_all(output_dir).map_err(|error| file_error(output_dir, error))?;
+
+        for mut report in grapher.analyze_many(&sample_paths, reference_paths)? {
+            Cli::filter_report(args, &mut report);
+            let report_path: PathBuf = output_dir.join(format!("{}.json", report.sample_name()));
+            std::fs::write(&report_path, report.to_json()).map_err(|error| file_error(&report_path, error))?;
+        }
+
+        Ok(())
     }
 }
 
@@ -93,18 +316,17 @@ impl Cli {
     /// Parse the cli arguments and execute the requested commands.
     #[staticmethod]
     #[pyo3(name = "run_cli")]
-    fn run_cli_py(py: Python) {
-        let thread_handle: thread::JoinHandle<()> = thread::spawn(|| {
-            Cli::parse_cli(&std::env::args().collect::<Vec<String>>()[1..]);
+    fn run_cli_py(py: Python) -> u8 {
+        let thread_handle: thread::JoinHandle<u8> = thread::spawn(|| {
+            Cli::parse_cli(&std::env::args().collect::<Vec<String>>()[1..])
         });
 
         loop {
-            if py.check_signals().is_err() { break; }
+            if py.check_signals().is_err() { return 0; }
             if thread_handle.is_finished() {
-                let _ = thread_handle.join();
-                break;
+                return thread_handle.join().unwrap_or_default();
             }
-            thread::sleep(Duration::from_millis(1));
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
         }
     }
 }
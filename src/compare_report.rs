@@ -1,41 +1,373 @@
-use std::time::Duration;
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    time::Duration,
+};
 
-use pyo3::{pyclass, pymethods};
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+
+use pyo3::{pyclass, pymethods, PyRef, PyResult};
 use serde::{Deserialize, Serialize};
+use serde_json::{de::IoRead, Number, StreamDeserializer, Value};
+
+use crate::control_flow_graph::NormalizationMode;
+use crate::disassembly::Disassembly;
+use crate::error::Error;
+use crate::grapher::{Alignment, BlockWeighting, GraphNorm, Pooling};
+use crate::r#match::{Binary as BinaryMatch, Method as MethodMatch, Package as PackageMatch};
+use crate::report_diff::ReportDiff;
+
+/// Settings of the grapher a compare report was produced with.
+#[pyclass]
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+pub struct CompareConfig {
+    #[pyo3(get)]
+    pub(crate) threshold: f32,
+    pub(crate) normalization: NormalizationMode,
+    pub(crate) pooling: Pooling,
+    #[pyo3(get)]
+    pub(crate) skip_self: bool,
+    #[pyo3(get)]
+    pub(crate) idf_weighted: bool,
+    #[pyo3(get)]
+    pub(crate) confidence_scale: f32,
+    #[pyo3(get)]
+    pub(crate) block_alignment: bool,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub(crate) fuzzy_bytes: bool,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub(crate) fast_block_overlap: bool,
+    #[serde(default)]
+    pub(crate) alignment: Alignment,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub(crate) size_ratio_gate: Option<f32>,
+    #[serde(default)]
+    pub(crate) graph_norm: GraphNorm,
+    #[serde(default)]
+    pub(crate) block_weighting: BlockWeighting,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub(crate) strict: bool,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub(crate) epsilon: f32,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub(crate) report_floor: f32,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub(crate) prefix_blocks: Option<usize>,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub(crate) bloom_prefilter: Option<f32>,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub(crate) max_reference_graphs: Option<usize>,
+    #[pyo3(get)]
+    #[serde(default)]
+    pub(crate) chunk_size: Option<usize>,
+}
+
+impl CompareConfig {
+    /// Value at which matches were considered significant.
+    #[inline]
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// The normalization mode instructions were compared with.
+    #[inline]
+    pub fn normalization(&self) -> NormalizationMode {
+        self.normalization
+    }
 
-use crate::r#match::Binary as BinaryMatch;
+    /// The strategy block similarities were pooled with.
+    #[inline]
+    pub fn pooling(&self) -> Pooling {
+        self.pooling
+    }
+
+    /// Whether functions were skipped when matched against themselves.
+    #[inline]
+    pub fn skip_self(&self) -> bool {
+        self.skip_self
+    }
+
+    /// Whether instruction matches were weighted by their rarity.
+    #[inline]
+    pub fn idf_weighted(&self) -> bool {
+        self.idf_weighted
+    }
+
+    /// The block count `k` of the method match confidence.
+    #[inline]
+    pub fn confidence_scale(&self) -> f32 {
+        self.confidence_scale
+    }
+
+    /// Whether method matches recorded the alignment of their blocks.
+    #[inline]
+    pub fn block_alignment(&self) -> bool {
+        self.block_alignment
+    }
+
+    /// Whether instructions differing by a single byte were considered equal.
+    #[inline]
+    pub fn fuzzy_bytes(&self) -> bool {
+        self.fuzzy_bytes
+    }
+
+    /// Whether graphs were compared by the overlap of their block hashes only.
+    #[inline]
+    pub fn fast_block_overlap(&self) -> bool {
+        self.fast_block_overlap
+    }
+
+    /// How the blocks of two graphs were paired before their similarities were pooled.
+    #[inline]
+    pub fn alignment(&self) -> Alignment {
+        self.alignment
+    }
+
+    /// The block count ratio below which graph pairs were not compared.
+    #[inline]
+    pub fn size_ratio_gate(&self) -> Option<f32> {
+        self.size_ratio_gate
+    }
+
+    /// The block count graph similarities were normalized by.
+    #[inline]
+    pub fn graph_norm(&self) -> GraphNorm {
+        self.graph_norm
+    }
+
+    /// How the best block similarities were weighted when pooled into graph similarities.
+    #[inline]
+    pub fn block_weighting(&self) -> BlockWeighting {
+        self.block_weighting
+    }
+
+    /// Whether every graph similarity was checked to be finite and within `[0, 1]`.
+    #[inline]
+    pub fn strict(&self) -> bool {
+        self.strict
+    }
+
+    /// The tolerance of the similarity comparisons, `0` in older reports which compared exactly.
+    #[inline]
+    pub fn epsilon(&self) -> f32 {
+        self.epsilon
+    }
+
+    /// The similarity below which method matches were left out of the report, after aggregation.
+    #[inline]
+    pub fn report_floor(&self) -> f32 {
+        self.report_floor
+    }
+
+    /// The number of leading blocks graph comparisons were limited to, if any.
+    #[inline]
+    pub fn prefix_blocks(&self) -> Option<usize> {
+        self.prefix_blocks
+    }
+
+    /// The estimated key overlap below which graph pairs were rejected without being compared.
+    #[inline]
+    pub fn bloom_prefilter(&self) -> Option<f32> {
+        self.bloom_prefilter
+    }
+
+    /// The number of leading graphs of each reference that were compared, if limited.
+    #[inline]
+    pub fn max_reference_graphs(&self) -> Option<usize> {
+        self.max_reference_graphs
+    }
+
+    /// The number of blocks whose best similarities were computed at once, if chunked.
+    #[inline]
+    pub fn chunk_size(&self) -> Option<usize> {
+        self.chunk_size
+    }
+}
+
+#[pymethods]
+impl CompareConfig {
+    fn __repr__(&self) -> String {
+        format!("CompareConfig(threshold={}, pooling={:?})", self.threshold, self.pooling)
+    }
+
+    #[getter]
+    fn mask_immediates(&self) -> bool {
+        self.normalization.mask_immediates
+    }
+
+    #[getter]
+    fn use_call_targets(&self) -> bool {
+        self.normalization.use_call_targets
+    }
+
+    #[getter]
+    fn cross_arch(&self) -> bool {
+        self.normalization.cross_arch
+    }
+
+    #[getter]
+    fn min_block_instructions(&self) -> usize {
+        self.normalization.min_block_instructions
+    }
+
+    #[getter]
+    fn strip_nops(&self) -> bool {
+        self.normalization.strip_nops
+    }
+
+    #[getter(pooling)]
+    fn py_pooling(&self) -> &'static str {
+        match self.pooling {
+            Pooling::Mean => "mean",
+            Pooling::Max => "max",
+            Pooling::TopK(_) => "top_k",
+        }
+    }
+
+    #[getter(alignment)]
+    fn py_alignment(&self) -> &'static str {
+        match self.alignment {
+            Alignment::Greedy => "greedy",
+            Alignment::Hungarian => "hungarian",
+        }
+    }
+
+    #[getter(graph_norm)]
+    fn py_graph_norm(&self) -> &'static str {
+        match self.graph_norm {
+            GraphNorm::Min => "min",
+            GraphNorm::Max => "max",
+            GraphNorm::Mean => "mean",
+        }
+    }
+
+    #[getter(block_weighting)]
+    fn py_block_weighting(&self) -> &'static str {
+        match self.block_weighting {
+            BlockWeighting::Uniform => "uniform",
+            BlockWeighting::InstructionCount => "instruction_count",
+        }
+    }
+
+    #[getter]
+    fn top_k(&self) -> Option<usize> {
+        match self.pooling {
+            Pooling::TopK(k) => Some(k.get()),
+            _ => None,
+        }
+    }
+}
+
+// Leading fields of a serialized report, read before the report itself to check its version.
+#[derive(Deserialize)]
+struct ReportHeader {
+    #[serde(default)]
+    schema_version: u32,
+}
 
 /// GoGrapher compare report data model.
 #[pyclass]
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct CompareReport {
+    #[pyo3(get)]
+    #[serde(default)]
+    schema_version: u32,
     #[pyo3(get)]
     sample_name: String,
     #[pyo3(get)]
+    #[serde(default)]
+    sample_format: String,
+    #[pyo3(get)]
+    #[serde(default)]
+    sample_arch: String,
+    #[pyo3(get)]
+    #[serde(default)]
+    sample_bits: u8,
+    #[pyo3(get)]
     matches: Vec<BinaryMatch>,
     compute_time: Duration,
+    #[pyo3(get)]
+    #[serde(default)]
+    config: CompareConfig,
 }
 
 impl CompareReport {
+    /// Number of decimals similarities are rounded to in the JSON representation.
+    pub const DEFAULT_JSON_PRECISION: u32 = 4;
+
+    /// Similarity difference below which binary matches are considered unchanged by `diff`.
+    pub const DEFAULT_DIFF_EPSILON: f32 = 1e-4;
+
+    /// Version of the serialized report format, bumped whenever its fields change.
+    ///
+    /// Reports written before the format was versioned are read as version `0`.
+    pub const SCHEMA_VERSION: u32 = 3;
+
     /// Create a new instance of the CompareReport data model.
     pub fn new(
-        sample_name: &str,
+        sample: &Disassembly,
         matches: Vec<BinaryMatch>,
         compute_time: Duration,
+        config: CompareConfig,
     ) -> Self {
         Self {
-            sample_name: sample_name.to_string(),
+            schema_version: CompareReport::SCHEMA_VERSION,
+            sample_name: sample.name().to_string(),
+            sample_format: sample.format().to_string(),
+            sample_arch: sample.arch().to_string(),
+            sample_bits: sample.bits(),
             matches,
             compute_time,
+            config,
         }
     }
 
+    /// The schema version of the report, see `SCHEMA_VERSION`.
+    #[inline]
+    pub fn schema_version(&self) -> u32 {
+        self.schema_version
+    }
+
     /// The name of the sample this report belongs to.
     #[inline]
     pub fn sample_name(&self) -> &String {
         &self.sample_name
     }
 
+    /// Change the name of the sample this report belongs to.
+    pub fn set_sample_name(&mut self, name: &str) {
+        self.sample_name = name.to_string();
+    }
+
+    /// Binary format of the sample (eg: "pe", "elf").
+    #[inline]
+    pub fn sample_format(&self) -> &String {
+        &self.sample_format
+    }
+
+    /// Architecture of the sample (eg: "amd64", "arm64").
+    #[inline]
+    pub fn sample_arch(&self) -> &String {
+        &self.sample_arch
+    }
+
+    /// Bitness of the sample.
+    #[inline]
+    pub fn sample_bits(&self) -> u8 {
+        self.sample_bits
+    }
+
     /// The set of match results per GO version.
     #[inline]
     pub fn matches(&self) -> &Vec<BinaryMatch> {
@@ -48,27 +380,708 @@ impl CompareReport {
         &self.compute_time
     }
 
+    /// The settings of the grapher the report was produced with.
+    #[inline]
+    pub fn config(&self) -> &CompareConfig {
+        &self.config
+    }
+
+    /// Number of references compared per second, 0.0 if no reference or no time was recorded.
+    pub fn throughput(&self) -> f64 {
+        let seconds: f64 = self.compute_time.as_secs_f64();
+        if self.matches.is_empty() || seconds == 0.0 {
+            return 0.0;
+        }
+        self.matches.len() as f64 / seconds
+    }
+
+    /// Average compute time spent per reference, zero if no reference was compared.
+    pub fn avg_time_per_reference(&self) -> Duration {
+        if self.matches.is_empty() {
+            return Duration::ZERO;
+        }
+        self.compute_time.div_f64(self.matches.len() as f64)
+    }
+
+    /// Append the matches of another report for the same sample to this report.
+    ///
+    /// The compute time of `other` is added to this report's compute time.
+    pub(crate) fn append(&mut self, other: CompareReport) -> Result<(), Error> {
+        if other.sample_name != self.sample_name {
+            return Err(Error::SampleNameMismatch {
+                expected: self.sample_name.clone(),
+                found: other.sample_name,
+            });
+        }
+
+        self.matches.extend(other.matches);
+        self.compute_time += other.compute_time;
+        Ok(())
+    }
+
+    /// Sort the binary matches by decreasing similarity, the mean similarity of their methods.
+    ///
+    /// Favors references whose matched functions are near identical, regardless of how much of
+    /// the reference was matched.
+    pub fn sort_by_similarity(&mut self) {
+        self.sort_binaries_by(BinaryMatch::similarity);
+    }
+
+    /// Sort the binary matches by decreasing coverage, the `containment` of the reference.
+    ///
+    /// Favors references most of whose functions are present in the sample, ranking candidate
+    /// libraries by how much of them is embedded rather than by how close their matches are.
+    pub fn sort_by_coverage(&mut self) {
+        self.sort_binaries_by(BinaryMatch::containment);
+    }
+
+    // Sort the binary matches by decreasing `key`, NaN values last.
+    fn sort_binaries_by(&mut self, key: fn(&BinaryMatch) -> f32) {
+        let key = |binary: &BinaryMatch| -> f32 {
+            let value: f32 = key(binary);
+            if value.is_nan() { f32::NEG_INFINITY } else { value }
+        };
+        self.matches.sort_by(|lhs, rhs| key(rhs).total_cmp(&key(lhs)));
+    }
+
+    /// Remove the references whose similarity to the sample is below `min_similarity`.
+    pub(crate) fn retain_binaries(&mut self, min_similarity: f32) {
+        self.matches.retain(|binary| binary.similarity() >= min_similarity);
+    }
+
+    /// Count the similarity of every method match of the report into `buckets` equal-width buckets
+    /// over `[0.0, 1.0]`.
+    ///
+    /// Similarities of 1.0 fall in the last bucket, non-finite similarities are ignored.
+    pub fn similarity_histogram(&self, buckets: usize) -> Vec<usize> {
+        let mut histogram: Vec<usize> = vec![0; buckets];
+        if buckets == 0 {
+            return histogram;
+        }
+
+        for method in self.matches.iter().flat_map(|binary| binary.matches()) {
+            let similarity: f32 = method.similarity();
+            if !similarity.is_finite() {
+                continue;
+            }
+            let bucket: usize = (similarity.clamp(0.0, 1.0) * buckets as f32) as usize;
+            histogram[bucket.min(buckets - 1)] += 1;
+        }
+        histogram
+    }
+
+    /// List the binary matches that appeared, disappeared or changed similarity in `other`.
+    ///
+    /// Similarities are compared with `CompareReport::DEFAULT_DIFF_EPSILON`.
+    pub fn diff(&self, other: &CompareReport) -> ReportDiff {
+        self.diff_with_epsilon(other, CompareReport::DEFAULT_DIFF_EPSILON)
+    }
+
+    /// List the binary matches that appeared, disappeared or changed similarity by more than
+    /// `epsilon` in `other`, binary matches being identified by their reference name.
+    pub fn diff_with_epsilon(&self, other: &CompareReport, epsilon: f32) -> ReportDiff {
+        let find = |report: &CompareReport, dest: &str| -> Option<f32> {
+            report
+                .matches
+                .iter()
+                .find(|binary| binary.dest() == dest)
+                .map(|binary| binary.similarity())
+        };
+
+        let mut appeared: Vec<String> = Vec::new();
+        let mut changed: Vec<(String, f32, f32)> = Vec::new();
+        for binary in &other.matches {
+            match find(self, binary.dest()) {
+                None => appeared.push(binary.dest().clone()),
+                Some(old) => {
+                    let new: f32 = binary.similarity();
+                    // Reports of references without matches hold NaN similarities.
+                    if (old - new).abs() > epsilon || old.is_nan() != new.is_nan() {
+                        changed.push((binary.dest().clone(), old, new));
+                    }
+                }
+            }
+        }
+        let disappeared: Vec<String> = self
+            .matches
+            .iter()
+            .filter(|binary| find(other, binary.dest()).is_none())
+            .map(|binary| binary.dest().clone())
+            .collect();
+
+        ReportDiff::new(appeared, disappeared, changed)
+    }
+
     /// Returns the JSON representation the the compare report.
+    ///
+    /// Similarities are rounded to `CompareReport::DEFAULT_JSON_PRECISION` decimals.
     pub fn to_json(&self) -> String {
-        serde_json::to_string_pretty(self).expect("Failed to serialize")
+        self.to_json_with_precision(Some(CompareReport::DEFAULT_JSON_PRECISION))
+    }
+
+    /// Returns the JSON representation the the compare report, with the similarity, confidence and
+    /// containment scores rounded to `precision` decimals or left at full precision if `None`.
+    ///
+    /// Only the serialized form is rounded, the report itself keeps full precision. The config and
+    /// timing values are never rounded, so that the report reads back with the same options.
+    pub fn to_json_with_precision(&self, precision: Option<u32>) -> String {
+        let mut value: Value = serde_json::to_value(self).expect("Failed to serialize");
+        if let Some(precision) = precision {
+            round_floats(&mut value, 10_f64.powi(precision as i32));
+        }
+        serde_json::to_string_pretty(&value).expect("Failed to serialize")
     }
 
     /// Parse a CompareReport from its JSON representation.
-    pub fn from_json(json_data: &str) -> Self {
-        serde_json::from_str(json_data).expect("Failed to deserialize")
+    ///
+    /// Reports of an older schema version are migrated to the current one, newer reports are
+    /// rejected as their fields could not be represented.
+    pub fn from_json(json_data: &str) -> Result<Self, Error> {
+        CompareReport::parse_json(json_data, "<json>")
+    }
+
+    // Parse a CompareReport from the JSON read from `path`, named in the errors.
+    fn parse_json(json_data: &str, path: &str) -> Result<Self, Error> {
+        let invalid_report = |error: serde_json::Error| Error::InvalidReport {
+            path: path.to_string(),
+            reason: error.to_string(),
+        };
+
+        let header: ReportHeader = serde_json::from_str(json_data).map_err(invalid_report)?;
+        CompareReport::check_schema_version(header.schema_version)?;
+        let report: CompareReport = serde_json::from_str(json_data).map_err(invalid_report)?;
+        Ok(report.migrated())
+    }
+
+    /// Write the gzip compressed JSON representation of the compare report to `path`.
+    pub fn to_json_gz(&self, path: &Path) -> Result<(), Error> {
+        let file_error = |error: std::io::Error| Error::FileAccess {
+            path: path.to_string_lossy().to_string(),
+            reason: error.to_string(),
+        };
+
+        let out_file: File = File::create(path).map_err(file_error)?;
+        let mut encoder: GzEncoder<File> = GzEncoder::new(out_file, Compression::default());
+        encoder.write_all(self.to_json().as_bytes()).map_err(file_error)?;
+        encoder.finish().map_err(file_error)?;
+        Ok(())
+    }
+
+    /// Parse a CompareReport from the gzip compressed JSON file at `path`.
+    pub fn from_json_gz(path: &Path) -> Result<Self, Error> {
+        let file_error = |error: std::io::Error| Error::FileAccess {
+            path: path.to_string_lossy().to_string(),
+            reason: error.to_string(),
+        };
+
+        let in_file: File = File::open(path).map_err(file_error)?;
+        let mut json_data: String = String::new();
+        GzDecoder::new(BufReader::new(in_file))
+            .read_to_string(&mut json_data)
+            .map_err(file_error)?;
+        CompareReport::parse_json(&json_data, &path.to_string_lossy())
+    }
+
+    /// Iterate over the `BinaryMatch` of each line of a JSONL file, such as a checkpoint.
+    ///
+    /// The file is read through a buffer and parsed one match at a time, so that multi-GB files are
+    /// processed with bounded memory. Files ending with ".gz" are transparently decompressed.
+    pub fn iter_jsonl(path: &Path) -> Result<BinaryMatchReader, Error> {
+        let in_file: File = File::open(path).map_err(|error| Error::FileAccess {
+            path: path.to_string_lossy().to_string(),
+            reason: error.to_string(),
+        })?;
+
+        let reader: Box<dyn BufRead + Send + Sync> = if path.extension().is_some_and(|extension| extension == "gz") {
+            Box::new(BufReader::new(GzDecoder::new(BufReader::new(in_file))))
+        } else {
+            Box::new(BufReader::new(in_file))
+        };
+
+        Ok(BinaryMatchReader {
+            path: path.to_path_buf(),
+            matches: Some(serde_json::Deserializer::from_reader(reader).into_iter()),
+        })
+    }
+
+    /// Returns the YAML representation the the compare report.
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(self).expect("Failed to serialize")
+    }
+
+    /// Parse a CompareReport from its YAML representation, see `from_json` for the versioning.
+    pub fn from_yaml(yaml_data: &str) -> Result<Self, Error> {
+        let invalid_report = |error: serde_yaml::Error| Error::InvalidReport {
+            path: "<yaml>".to_string(),
+            reason: error.to_string(),
+        };
+
+        let header: ReportHeader = serde_yaml::from_str(yaml_data).map_err(invalid_report)?;
+        CompareReport::check_schema_version(header.schema_version)?;
+        let report: CompareReport = serde_yaml::from_str(yaml_data).map_err(invalid_report)?;
+        Ok(report.migrated())
+    }
+
+    // Reject the reports whose schema version is newer than the supported one.
+    fn check_schema_version(schema_version: u32) -> Result<(), Error> {
+        if schema_version > CompareReport::SCHEMA_VERSION {
+            return Err(Error::UnsupportedReportVersion {
+                found: schema_version,
+                supported: CompareReport::SCHEMA_VERSION,
+            });
+        }
+        Ok(())
+    }
+
+    // Upgrade a report of an older schema version to the current one.
+    //
+    // Version 3 added the function counts and compute times of the binary matches, the `exact` flag
+    // and `reference` of the method matches, and the comparison options to the config. These fields
+    // are already defaulted when parsed, only the references are filled, from their binary match.
+    fn migrated(mut self) -> Self {
+        if self.schema_version < 3 {
+            self.matches = self.matches.into_iter().map(BinaryMatch::with_method_references).collect();
+        }
+        self.schema_version = CompareReport::SCHEMA_VERSION;
+        self
+    }
+
+    // The (malware_offset, resolved_name, reference) of the sample functions to rename, sorted by
+    // offset.
+    //
+    // A function matched by several references takes the name of its most similar match.
+    fn renames(&self, min_similarity: f32) -> Vec<(u64, &String, &String)> {
+        let mut best: HashMap<u64, &MethodMatch> = HashMap::new();
+        for method in self.matches.iter().flat_map(BinaryMatch::matches) {
+            if method.similarity() < min_similarity || method.resolved_name().is_empty() {
+                continue;
+            }
+            let entry = best.entry(method.malware_offset()).or_insert(method);
+            if method.similarity() > entry.similarity() {
+                *entry = method;
+            }
+        }
+
+        let mut renames: Vec<(u64, &String, &String)> = best
+            .into_iter()
+            .map(|(offset, method)| (offset, method.resolved_name(), method.reference()))
+            .collect();
+        renames.sort_unstable();
+        renames
+    }
+
+    /// The resolved names matched by more than one malware function, with the offsets of these
+    /// functions.
+    ///
+    /// Often the sign of over-matching at a low threshold, these names would be applied to several
+    /// functions by the rename scripts. Sorted by name, offsets in ascending order.
+    pub fn ambiguous_resolutions(&self) -> Vec<(String, Vec<u64>)> {
+        let mut offsets: HashMap<&String, Vec<u64>> = HashMap::new();
+        for method in self.matches.iter().flat_map(BinaryMatch::matches) {
+            offsets.entry(method.resolved_name()).or_default().push(method.malware_offset());
+        }
+
+        let mut ambiguous: Vec<(String, Vec<u64>)> = offsets
+            .into_iter()
+            .filter_map(|(name, mut offsets)| {
+                offsets.sort_unstable();
+                offsets.dedup();
+                (offsets.len() > 1).then(|| (name.clone(), offsets))
+            })
+            .collect();
+        ambiguous.sort_unstable();
+        ambiguous
+    }
+
+    /// Summary of the method matches grouped by the Go package of their resolved name.
+    ///
+    /// The package path is derived from the resolved names, see `PackageMatch::package_path`,
+    /// names without a package are left out. Sorted by decreasing match count, then by package.
+    pub fn package_summary(&self) -> Vec<PackageMatch> {
+        let mut packages: HashMap<&str, Vec<&MethodMatch>> = HashMap::new();
+        for method in self.matches.iter().flat_map(BinaryMatch::matches) {
+            if let Some(package) = PackageMatch::package_path(method.resolved_name()) {
+                packages.entry(package).or_default().push(method);
+            }
+        }
+
+        let mut summary: Vec<PackageMatch> = packages
+            .into_iter()
+            .map(|(package, matches)| PackageMatch::new(package, &matches))
+            .collect();
+        summary.sort_by(|lhs, rhs| {
+            rhs.match_count()
+                .cmp(&lhs.match_count())
+                .then_with(|| lhs.package().cmp(rhs.package()))
+        });
+        summary
+    }
+
+    /// Returns a new Disassembly composed of the `sample` graphs matched with a similarity of at
+    /// least `min_similarity`, for a focused re-analysis.
+    ///
+    /// The `sample` is the disassembly the report was produced from, its graphs are selected by the
+    /// `malware_offset` of the matches.
+    pub fn extract_matched(&self, sample: &Disassembly, min_similarity: f32) -> Disassembly {
+        let offsets: HashSet<u64> = self
+            .matches
+            .iter()
+            .flat_map(BinaryMatch::matches)
+            .filter(|method| method.similarity() >= min_similarity)
+            .map(|method| method.malware_offset())
+            .collect();
+
+        Disassembly {
+            name: sample.name.clone(),
+            path: sample.path.clone(),
+            graphs: sample
+                .graphs
+                .iter()
+                .filter(|graph| offsets.contains(&graph.offset))
+                .cloned()
+                .collect(),
+            format: sample.format.clone(),
+            arch: sample.arch.clone(),
+            bits: sample.bits,
+            normalization: sample.normalization,
+        }
+    }
+
+    /// Returns an IDAPython script renaming the sample's functions after their matches.
+    ///
+    /// Only matches with a similarity of at least `min_similarity` are applied.
+    pub fn to_ida_script(&self, min_similarity: f32) -> String {
+        let mut script: String = format!(
+            "# Generated by GoGrapher from the compare report of {:?}.\nimport idc\n\nRENAMES = [\n",
+            self.sample_name
+        );
+        for (offset, name, reference) in self.renames(min_similarity) {
+            let name: String = serde_json::to_string(name).expect("Failed to serialize");
+            script.push_str(&format!("    ({offset:#x}, {name}),  # {}\n", script_comment(reference)));
+        }
+        script.push_str("]\n\nfor address, name in RENAMES:\n");
+        script.push_str("    idc.set_name(address, name, idc.SN_NOWARN | idc.SN_FORCE)\n");
+        script
+    }
+
+    /// Returns a Ghidra Java script renaming the sample's functions after their matches.
+    ///
+    /// Only matches with a similarity of at least `min_similarity` are applied, functions missing
+    /// from the Ghidra program are created.
+    pub fn to_ghidra_script(&self, min_similarity: f32) -> String {
+        let mut script: String = format!(
+            "// Generated by GoGrapher from the compare report of {:?}.\n",
+            self.sample_name
+        );
+        script.push_str(concat!(
+            "import ghidra.app.script.GhidraScript;\n",
+            "import ghidra.program.model.address.Address;\n",
+            "import ghidra.program.model.listing.Function;\n",
+            "import ghidra.program.model.symbol.SourceType;\n",
+            "\n",
+            "public class GoGrapherRename extends GhidraScript {\n",
+            "    @Override\n",
+            "    public void run() throws Exception {\n",
+        ));
+        for (offset, name, reference) in self.renames(min_similarity) {
+            let name: String = serde_json::to_string(name).expect("Failed to serialize");
+            script.push_str(&format!("        rename({offset:#x}L, {name}); // {}\n", script_comment(reference)));
+        }
+        script.push_str(concat!(
+            "    }\n",
+            "\n",
+            "    private void rename(long offset, String name) throws Exception {\n",
+            "        Address address = toAddr(offset);\n",
+            "        Function function = getFunctionAt(address);\n",
+            "        if (function == null) {\n",
+            "            function = createFunction(address, name);\n",
+            "        }\n",
+            "        if (function != null) {\n",
+            "            function.setName(name, SourceType.IMPORTED);\n",
+            "        }\n",
+            "    }\n",
+            "}\n",
+        ));
+        script
+    }
+}
+
+/// Returns `text` made safe to end a single line comment of the rename scripts.
+///
+/// Control characters, such as the `\r` and `\n` ending the comment, are replaced by spaces and
+/// backslashes are doubled: Java translates the `\u000a` unicode escapes of comments into line
+/// terminators, but not those whose backslash is itself escaped.
+fn script_comment(text: &str) -> String {
+    text.chars()
+        .map(|c| if c.is_control() { ' ' } else { c })
+        .collect::<String>()
+        .replace('\\', "\\\\")
+}
+
+/// Fields of the binary, method and package matches rounded by `CompareReport::to_json_with_precision`.
+const ROUNDED_FIELDS: [&str; 4] = ["similarity", "confidence", "containment", "mean_similarity"];
+
+/// Round the score fields of the matches of a serialized report, where `factor` is `10^precision`.
+fn round_floats(value: &mut Value, factor: f64) {
+    match value {
+        Value::Array(values) => values.iter_mut().for_each(|value| round_floats(value, factor)),
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                match value {
+                    Value::Number(number) if number.is_f64() && ROUNDED_FIELDS.contains(&key.as_str()) => {
+                        let rounded: f64 = (number.as_f64().expect("Float JSON number") * factor).round() / factor;
+                        if let Some(rounded) = Number::from_f64(rounded) {
+                            *number = rounded;
+                        }
+                    }
+                    // The config is never rounded.
+                    _ if key == "config" => {}
+                    _ => round_floats(value, factor),
+                }
+            }
+        }
+        _ => {}
     }
 }
 
 #[pymethods]
 impl CompareReport {
-    #[pyo3(name = "to_json")]
-    fn py_to_json(&self) -> String {
-        self.to_json()
+    #[classattr]
+    #[pyo3(name = "SCHEMA_VERSION")]
+    fn schema_version_py() -> u32 {
+        CompareReport::SCHEMA_VERSION
+    }
+
+    fn __repr__(&self) -> String {
+        format!("CompareReport(sample='{}', references={})", self.sample_name, self.matches.len())
+    }
+
+    #[pyo3(name = "set_sample_name")]
+    fn py_set_sample_name(&mut self, name: &str) {
+        self.set_sample_name(name)
+    }
+
+    #[pyo3(name = "throughput")]
+    fn py_throughput(&self) -> f64 {
+        self.throughput()
+    }
+
+    #[pyo3(name = "avg_time_per_reference")]
+    fn py_avg_time_per_reference(&self) -> f64 {
+        self.avg_time_per_reference().as_secs_f64()
+    }
+
+    #[pyo3(name = "sort_by_similarity")]
+    fn py_sort_by_similarity(&mut self) {
+        self.sort_by_similarity()
+    }
+
+    #[pyo3(name = "sort_by_coverage")]
+    fn py_sort_by_coverage(&mut self) {
+        self.sort_by_coverage()
+    }
+
+    #[pyo3(name = "similarity_histogram")]
+    fn py_similarity_histogram(&self, buckets: usize) -> Vec<usize> {
+        self.similarity_histogram(buckets)
+    }
+
+    #[pyo3(name = "diff", signature = (other, epsilon=CompareReport::DEFAULT_DIFF_EPSILON))]
+    fn py_diff(&self, other: &CompareReport, epsilon: f32) -> ReportDiff {
+        self.diff_with_epsilon(other, epsilon)
+    }
+
+    #[pyo3(name = "to_json", signature = (precision=Some(CompareReport::DEFAULT_JSON_PRECISION)))]
+    fn py_to_json(&self, precision: Option<u32>) -> String {
+        self.to_json_with_precision(precision)
     }
 
     #[staticmethod]
     #[pyo3(name = "from_json")]
-    fn py_from_json(json_data: &str) -> Self {
-        CompareReport::from_json(json_data)
+    fn py_from_json(json_data: &str) -> PyResult<Self> {
+        Ok(CompareReport::from_json(json_data)?)
+    }
+
+    #[pyo3(name = "to_json_gz")]
+    fn py_to_json_gz(&self, path: PathBuf) -> PyResult<()> {
+        Ok(self.to_json_gz(&path)?)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_json_gz")]
+    fn py_from_json_gz(path: PathBuf) -> PyResult<Self> {
+        Ok(CompareReport::from_json_gz(&path)?)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "iter_jsonl")]
+    fn py_iter_jsonl(path: PathBuf) -> PyResult<BinaryMatchReader> {
+        Ok(CompareReport::iter_jsonl(&path)?)
+    }
+
+    #[pyo3(name = "to_yaml")]
+    fn py_to_yaml(&self) -> String {
+        self.to_yaml()
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "from_yaml")]
+    fn py_from_yaml(yaml_data: &str) -> PyResult<Self> {
+        Ok(CompareReport::from_yaml(yaml_data)?)
+    }
+
+    #[pyo3(name = "package_summary")]
+    fn py_package_summary(&self) -> Vec<PackageMatch> {
+        self.package_summary()
+    }
+
+    #[pyo3(name = "ambiguous_resolutions")]
+    fn py_ambiguous_resolutions(&self) -> Vec<(String, Vec<u64>)> {
+        self.ambiguous_resolutions()
+    }
+
+    #[pyo3(name = "extract_matched", signature = (sample, min_similarity=0.0))]
+    fn py_extract_matched(&self, sample: PyRef<Disassembly>, min_similarity: f32) -> Disassembly {
+        self.extract_matched(&sample, min_similarity)
+    }
+
+    #[pyo3(name = "to_ida_script", signature = (min_similarity=0.0))]
+    fn py_to_ida_script(&self, min_similarity: f32) -> String {
+        self.to_ida_script(min_similarity)
+    }
+
+    #[pyo3(name = "to_ghidra_script", signature = (min_similarity=0.0))]
+    fn py_to_ghidra_script(&self, min_similarity: f32) -> String {
+        self.to_ghidra_script(min_similarity)
+    }
+}
+
+/// Streaming reader of the `BinaryMatch` of each line of a JSONL file, see `CompareReport::iter_jsonl`.
+///
+/// Iteration stops after the first error, as the rest of the stream can not be resynchronized.
+#[pyclass]
+pub struct BinaryMatchReader {
+    path: PathBuf,
+    matches: Option<StreamDeserializer<'static, IoRead<Box<dyn BufRead + Send + Sync>>, BinaryMatch>>,
+}
+
+impl Iterator for BinaryMatchReader {
+    type Item = Result<BinaryMatch, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.matches.as_mut()?.next()? {
+            // Matches written before method matches recorded their reference.
+            Ok(binary) => Some(Ok(binary.with_method_references())),
+            Err(error) => {
+                self.matches = None;
+                let path: String = self.path.to_string_lossy().to_string();
+                Some(Err(if error.is_io() {
+                    Error::FileAccess { path, reason: error.to_string() }
+                } else {
+                    Error::InvalidReport { path, reason: error.to_string() }
+                }))
+            }
+        }
+    }
+}
+
+#[pymethods]
+impl BinaryMatchReader {
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> PyResult<Option<BinaryMatch>> {
+        Ok(self.next().transpose()?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::grapher::Grapher;
+    use crate::test_utils::{chain_graph, disassembly, keyed_block};
+
+    #[test]
+    fn from_json_rejects_invalid_reports() {
+        let is_invalid = |error: Error| matches!(error, Error::InvalidReport { path, .. } if path == "<json>");
+
+        // Data that is not JSON, truncated, and a valid header without the report's fields.
+        assert!(CompareReport::from_json("not json").is_err_and(is_invalid));
+        assert!(CompareReport::from_json(r#"{"schema_version": 1, "sample": "#).is_err_and(is_invalid));
+        assert!(CompareReport::from_json(r#"{"schema_version": 1}"#).is_err_and(is_invalid));
+        assert!(matches!(
+            CompareReport::from_json(r#"{"schema_version": 4294967295}"#),
+            Err(Error::UnsupportedReportVersion { .. }),
+        ));
+    }
+
+    #[test]
+    fn from_yaml_rejects_invalid_reports() {
+        let is_invalid = |error: Error| matches!(error, Error::InvalidReport { path, .. } if path == "<yaml>");
+
+        // Data that is not a mapping, unbalanced, and a valid header without the report's fields.
+        assert!(CompareReport::from_yaml("- not a report").is_err_and(is_invalid));
+        assert!(CompareReport::from_yaml("schema_version: [1").is_err_and(is_invalid));
+        assert!(CompareReport::from_yaml("schema_version: 1").is_err_and(is_invalid));
+        assert!(matches!(
+            CompareReport::from_yaml("schema_version: 4294967295"),
+            Err(Error::UnsupportedReportVersion { .. }),
+        ));
+    }
+
+    #[test]
+    fn to_json_with_precision_keeps_config() {
+        let graph = || chain_graph("main.f", 0x1000, vec![keyed_block(0x1000, &["55", "c3"])]);
+        let sample: Disassembly = disassembly("sample", vec![graph()]);
+        let reference: Disassembly = disassembly("reference", vec![graph()]);
+        let report: CompareReport = Grapher::new(0.123456, false)
+            .with_epsilon(1e-6)
+            .unwrap()
+            .compare(&sample, vec![&reference])
+            .unwrap();
+
+        let read: CompareReport = CompareReport::from_json(&report.to_json_with_precision(Some(2))).unwrap();
+        assert_eq!(read.config().epsilon(), 1e-6);
+        assert_eq!(read.config().threshold(), 0.123456);
+        assert_eq!(read.compute_time, report.compute_time);
+        assert_eq!(read.matches[0].similarity(), 1.0);
+    }
+
+    #[test]
+    fn from_json_fills_the_references_of_older_reports() {
+        let graph = || chain_graph("main.f", 0x1000, vec![keyed_block(0x1000, &["55", "c3"])]);
+        let sample: Disassembly = disassembly("sample", vec![graph()]);
+        let reference: Disassembly = disassembly("reference", vec![graph()]);
+        let report: CompareReport = Grapher::new(0.5, false).compare(&sample, vec![&reference]).unwrap();
+
+        // A version 2 report, written before method matches recorded their reference.
+        let mut value: Value = serde_json::to_value(&report).unwrap();
+        value["schema_version"] = 2.into();
+        for method in value["matches"][0]["matches"].as_array_mut().unwrap() {
+            method.as_object_mut().unwrap().remove("reference");
+        }
+
+        let read: CompareReport = CompareReport::from_json(&value.to_string()).unwrap();
+        assert_eq!(read.schema_version(), CompareReport::SCHEMA_VERSION);
+        assert_eq!(read.matches[0].matches()[0].reference(), "reference");
+    }
+
+    #[test]
+    fn rename_scripts_escape_reference_comments() {
+        let graph = || chain_graph("main.f", 0x1000, vec![keyed_block(0x1000, &["55", "c3"])]);
+        let sample: Disassembly = disassembly("sample", vec![graph()]);
+        // A reference name breaking out of the comments through a line feed, a carriage return
+        // and a Java unicode escape.
+        let reference: Disassembly = disassembly("lib\nx = 1\rimport os\\u000aRuntime.exec();", vec![graph()]);
+        let report: CompareReport = Grapher::new(0.5, false).compare(&sample, vec![&reference]).unwrap();
+
+        let ida: String = report.to_ida_script(0.5);
+        let rename: &str = ida.lines().find(|line| line.contains("0x1000")).unwrap();
+        assert_eq!(rename, r#"    (0x1000, "main.f"),  # lib x = 1 import os\\u000aRuntime.exec();"#);
+
+        let ghidra: String = report.to_ghidra_script(0.5);
+        let rename: &str = ghidra.lines().find(|line| line.contains("0x1000L")).unwrap();
+        assert_eq!(rename, r#"        rename(0x1000L, "main.f"); // lib x = 1 import os\\u000aRuntime.exec();"#);
     }
 }
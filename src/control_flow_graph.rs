@@ -1,32 +1,226 @@
+use std::{collections::HashMap, sync::LazyLock};
+
 use chibihash::StreamingChibiHasher;
-use pyo3::pyclass;
+use pyo3::{exceptions::PyValueError, pyclass, pymethods, PyRef, PyResult};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
 use smda::function::Instruction;
 
+use crate::method_diff::BlockAlignment;
+
+/// Selects how instructions are normalized into the keys used for hashing and comparison.
+///
+/// Block and graph hashes are computed from the normalized keys, they are therefore only
+/// comparable between graphs normalized with the same mode.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NormalizationMode {
+    /// Zero the immediate / displacement bytes of each instruction.
+    pub mask_immediates: bool,
+    /// Append the resolved symbol name of a call's target to the call instruction's key.
+    #[serde(default)]
+    pub use_call_targets: bool,
+    /// Replace each instruction by its coarse operation class, takes precedence over `mask_immediates`.
+    #[serde(default)]
+    pub cross_arch: bool,
+    /// Blocks with fewer instructions are left out of the graph hash, they are still compared.
+    #[serde(default)]
+    pub min_block_instructions: usize,
+    /// Leave the NOP-class and padding instructions out of the keys, see `is_padding`.
+    #[serde(default)]
+    pub strip_nops: bool,
+}
+
+impl NormalizationMode {
+    /// Returns the comparison key of an instruction under this mode, `call_target` being the
+    /// symbol name of the instruction's target if it is a resolved call.
+    fn key(&self, instruction: &Instruction, call_target: Option<&str>) -> String {
+        let key: String = if self.cross_arch {
+            operation_class(instruction).to_string()
+        } else if self.mask_immediates {
+            mask_immediates(instruction)
+        } else {
+            instruction.bytes.clone()
+        };
+
+        match call_target {
+            Some(symbol) if self.use_call_targets => format!("{key}:{symbol}"),
+            _ => key,
+        }
+    }
+}
+
+/// Matches the hexadecimal literals of an instruction's operands, along with their sign.
+static HEX_LITERAL: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(r"(-\s*)?0x([0-9a-f]+)").expect("Failed to create regex")
+});
+
+/// Returns the instruction's hex encoded bytes with its immediate / displacement bytes zeroed.
+///
+/// The operands reported by smda are used to locate the literal values within the encoded
+/// instruction. Only hexadecimal literals are masked, small decimal values (scales, short stack
+/// offsets) are left untouched as they rarely vary between builds. Relative branch targets are
+/// converted back to their encoded displacement before being located.
+fn mask_immediates(instruction: &Instruction) -> String {
+    let mut bytes: Vec<u8> = (0..instruction.bytes.len() / 2)
+        .filter_map(|i| u8::from_str_radix(&instruction.bytes[i * 2..i * 2 + 2], 16).ok())
+        .collect();
+    let operands: &str = instruction.operands.as_deref().unwrap_or("");
+    let is_branch: bool = instruction.mnemonic.starts_with('j')
+        || instruction.mnemonic.starts_with("call")
+        || instruction.mnemonic.starts_with("loop");
+
+    for literal in HEX_LITERAL.captures_iter(operands) {
+        let Ok(mut value) = i128::from_str_radix(&literal[2], 16) else {
+            continue;
+        };
+        if literal.get(1).is_some() {
+            value = -value;
+        }
+        if is_branch {
+            value -= instruction.offset as i128 + bytes.len() as i128;
+        }
+
+        // Try the widest encoding first, skipping the leading opcode byte.
+        for width in [8_usize, 4, 2, 1] {
+            let bits: u32 = (width * 8) as u32;
+            let fits: bool = value >= -(1_i128 << (bits - 1)) && value < (1_i128 << bits);
+            if !fits || width >= bytes.len() {
+                continue;
+            }
+            let encoded: Vec<u8> = value.to_le_bytes()[..width].to_vec();
+            if let Some(position) = (1..=bytes.len() - width)
+                .rev()
+                .find(|&position| bytes[position..position + width] == encoded[..])
+            {
+                bytes[position..position + width].fill(0);
+                break;
+            }
+        }
+    }
+
+    bytes.iter().map(|byte| format!("{byte:02x}")).collect()
+}
+
+/// Returns the coarse operation class of an instruction, independent of its architecture.
+///
+/// The class is derived from the mnemonic and, for the x86 data transfers, from the position of
+/// the memory operand. This is approximate: the classes of two ISAs only loosely correspond, and
+/// sequences compiled for different architectures rarely have the same length, so similarities
+/// are only meaningful relative to one another.
+fn operation_class(instruction: &Instruction) -> &'static str {
+    let mnemonic: &str = instruction.mnemonic.as_str();
+    let operands: &str = instruction.operands.as_deref().unwrap_or("");
+    let mut parts = operands.split(',');
+    let destination_is_memory: bool = parts.next().is_some_and(|operand| operand.contains('['));
+    let source_is_memory: bool = parts.any(|operand| operand.contains('['));
+
+    match mnemonic {
+        "call" | "bl" | "blr" => "call",
+        "ret" | "retn" | "retf" => "ret",
+        "nop" | "endbr64" | "endbr32" => "nop",
+        "cmp" | "test" | "cmn" | "tst" | "bt" | "ccmp" => "compare",
+        "push" | "stp" | "str" | "strb" | "strh" | "stur" => "store",
+        "pop" | "ldp" | "ldr" | "ldrb" | "ldrh" | "ldrsw" | "ldur" | "adrp" => "load",
+        "b" | "br" | "cbz" | "cbnz" | "tbz" | "tbnz" => "branch",
+        _ if mnemonic.starts_with('j') || mnemonic.starts_with("loop") || mnemonic.starts_with("b.") => "branch",
+        _ if mnemonic.starts_with("nop") => "nop",
+        _ if mnemonic.starts_with("mov") || mnemonic.starts_with("cmov") || mnemonic == "xchg" => {
+            if destination_is_memory {
+                "store"
+            } else if source_is_memory {
+                "load"
+            } else {
+                "move"
+            }
+        }
+        _ if mnemonic.starts_with("ld") => "load",
+        _ if mnemonic.starts_with("st") => "store",
+        "add" | "adc" | "sub" | "sbb" | "inc" | "dec" | "neg" | "mul" | "imul" | "div" | "idiv" | "udiv"
+        | "sdiv" | "madd" | "msub" | "lea" | "adr" | "and" | "or" | "xor" | "not" | "orr" | "eor"
+        | "shl" | "shr" | "sar" | "sal" | "rol" | "ror" | "lsl" | "lsr" | "asr" | "adds" | "subs"
+        | "ands" => "arith",
+        _ => "other",
+    }
+}
+
+/// Whether an instruction is a NOP or padding, such as the alignment NOPs inserted by the Go
+/// compiler, which differ between builds without affecting the function's behavior.
+///
+/// Determined from the mnemonic alone: the `nop` family (including the multi-byte `nopw` / `nopl`
+/// forms), `fnop` and the `int3` padding found between functions.
+fn is_padding(instruction: &Instruction) -> bool {
+    let mnemonic: &str = instruction.mnemonic.as_str();
+    mnemonic.starts_with("nop") || mnemonic == "fnop" || mnemonic == "int3"
+}
+
+/// Returns the disassembly text of an instruction.
+fn instruction_text(instruction: &Instruction) -> String {
+    match &instruction.operands {
+        Some(operands) if !operands.is_empty() => format!("{} {operands}", instruction.mnemonic),
+        _ => instruction.mnemonic.clone(),
+    }
+}
+
 /// Data model of a Control Flow Graph's (CFG) basic block.
+#[pyclass]
 #[derive(Clone)]
 pub struct BasicBlock {
+    #[pyo3(get)]
     pub(crate) offset: u64,
     pub(crate) instructions: Vec<Instruction>,
+    #[pyo3(get)]
     pub(crate) in_refs: Vec<usize>,
+    #[pyo3(get)]
     pub(crate) out_refs: Vec<usize>,
+    #[pyo3(get)]
     pub(crate) hash: u64,
+    pub(crate) keys: Vec<String>,
+    pub(crate) call_targets: Vec<Option<String>>,
 }
 
 impl BasicBlock {
-    /// Create a new BasicBlock instance.
-    pub fn new(offset: u64, instructions: &[Instruction]) -> Self {
-        // Compute the hash of the block
-        let mut hasher: StreamingChibiHasher = StreamingChibiHasher::new(0x1337_u64);
-        for ins in instructions {
-            hasher.update(ins.bytes.as_bytes());
-        }
-        Self {
+    /// Create a new BasicBlock instance, normalizing its instructions with the given `mode`.
+    pub fn new(offset: u64, instructions: &[Instruction], mode: NormalizationMode) -> Self {
+        BasicBlock::with_call_targets(offset, instructions, vec![None; instructions.len()], mode)
+    }
+
+    /// Create a new BasicBlock instance along with the resolved symbol name of each instruction's
+    /// call target, `None` for instructions that are not resolved calls.
+    pub(crate) fn with_call_targets(
+        offset: u64,
+        instructions: &[Instruction],
+        call_targets: Vec<Option<String>>,
+        mode: NormalizationMode,
+    ) -> Self {
+        let mut block = Self {
             offset,
             instructions: instructions.to_vec(),
             in_refs: Vec::new(),
             out_refs: Vec::new(),
-            hash: hasher.finalize(),
+            hash: 0,
+            keys: Vec::new(),
+            call_targets,
+        };
+        block.normalize(mode);
+        block
+    }
+
+    /// Re-compute the instruction keys and the hash of the block for the given `mode`.
+    pub(crate) fn normalize(&mut self, mode: NormalizationMode) {
+        self.keys = self
+            .instructions
+            .iter()
+            .zip(&self.call_targets)
+            .filter(|(ins, _)| !(mode.strip_nops && is_padding(ins)))
+            .map(|(ins, call_target)| mode.key(ins, call_target.as_deref()))
+            .collect();
+
+        // Compute the hash of the block
+        let mut hasher: StreamingChibiHasher = StreamingChibiHasher::new(0x1337_u64);
+        for key in &self.keys {
+            hasher.update(key.as_bytes());
         }
+        self.hash = hasher.finalize();
     }
 
     /// Offset of the block relative to the ".text" segment.
@@ -53,38 +247,201 @@ impl BasicBlock {
         &self.out_refs
     }
 
-    /// Non-Cryptographic hash of the block's instructions.
+    /// Non-Cryptographic hash of the block's normalized instructions.
+    ///
+    /// The hash depends on the `NormalizationMode` the block was built with.
     #[inline]
     pub fn hash(&self) -> u64 {
         self.hash
     }
+
+    /// The normalized comparison key of each instruction within the block.
+    ///
+    /// NOP and padding instructions have no key when the block was normalized with `strip_nops`,
+    /// the keys then no longer line up with the instructions.
+    #[inline]
+    pub fn keys(&self) -> &Vec<String> {
+        &self.keys
+    }
+
+    /// The disassembly text of each instruction within the block.
+    pub fn mnemonics(&self) -> Vec<String> {
+        self.instructions.iter().map(instruction_text).collect()
+    }
+
+    /// The hex encoded bytes of each instruction within the block.
+    pub fn bytes_hex(&self) -> Vec<String> {
+        self.instructions.iter().map(|ins| ins.bytes.clone()).collect()
+    }
+
+    /// The concatenation of the block's normalized keys, in order, the exact data fed to its hash.
+    ///
+    /// Without normalization mode these are the hex encoded bytes of the instructions, the block's
+    /// hash being the streaming ChibiHash (seed `0x1337`) of this string's UTF-8 bytes.
+    pub fn concatenated_bytes(&self) -> String {
+        self.keys.concat()
+    }
+
+    // Share of normalized keys two blocks have in common, accounting for duplicates, over the larger block.
+    fn key_similarity(&self, other: &BasicBlock) -> f32 {
+        if self.hash == other.hash {
+            return 1.0;
+        }
+        let size: usize = self.keys.len().max(other.keys.len());
+        if size == 0 {
+            return 0.0;
+        }
+        let mut counts: HashMap<&String, usize> = HashMap::new();
+        for key in &self.keys {
+            *counts.entry(key).or_default() += 1;
+        }
+        let mut common: usize = 0;
+        for key in &other.keys {
+            if let Some(count) = counts.get_mut(key).filter(|count| **count > 0) {
+                *count -= 1;
+                common += 1;
+            }
+        }
+        common as f32 / size as f32
+    }
+}
+
+#[pymethods]
+impl BasicBlock {
+    fn __repr__(&self) -> String {
+        format!("BasicBlock(offset={:#x}, instructions={})", self.offset, self.instructions.len())
+    }
+
+    #[pyo3(name = "mnemonics")]
+    fn py_mnemonics(&self) -> Vec<String> {
+        self.mnemonics()
+    }
+
+    #[pyo3(name = "bytes_hex")]
+    fn py_bytes_hex(&self) -> Vec<String> {
+        self.bytes_hex()
+    }
+
+    #[pyo3(name = "concatenated_bytes")]
+    fn py_concatenated_bytes(&self) -> String {
+        self.concatenated_bytes()
+    }
+}
+
+/// Bloom filter of the instruction keys of a graph, used to estimate the overlap of two graphs.
+///
+/// 512 bits, each key setting two of them. A filter never misses a key it holds, but unrelated
+/// keys collide, more so in large graphs whose filters saturate: estimates are biased upwards.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub(crate) struct KeyFilter([u64; 8]);
+
+impl KeyFilter {
+    // Add a key to the filter.
+    fn insert(&mut self, key: &str) {
+        let mut hasher: StreamingChibiHasher = StreamingChibiHasher::new(0x1337_u64);
+        hasher.update(key.as_bytes());
+        let hash: u64 = hasher.finalize();
+        for bit in [hash & 0x1ff, (hash >> 32) & 0x1ff] {
+            self.0[(bit / 64) as usize] |= 1 << (bit % 64);
+        }
+    }
+
+    // Number of bits set in the filter.
+    fn count(&self) -> u32 {
+        self.0.iter().map(|word| word.count_ones()).sum()
+    }
+
+    /// Estimated fraction of the keys of the smaller filter held by the other, within `[0, 1]`.
+    ///
+    /// The bits both filters set over the bits set by the sparser one, `1.0` if either is empty.
+    pub(crate) fn overlap(&self, other: &KeyFilter) -> f32 {
+        let shared: u32 = self.0.iter().zip(&other.0).map(|(lhs, rhs)| (lhs & rhs).count_ones()).sum();
+        let sparser: u32 = std::cmp::min(self.count(), other.count());
+        if sparser == 0 {
+            return 1.0;
+        }
+        shared as f32 / sparser as f32
+    }
 }
 
 /// Control Flow Graph (CFG) data model.
 #[pyclass]
 #[derive(Clone)]
 pub struct ControlFlowGraph {
+    #[pyo3(get)]
     pub(crate) name: String,
+    #[pyo3(get)]
     pub(crate) offset: u64,
+    #[pyo3(get)]
     pub(crate) blocks: Vec<BasicBlock>,
+    #[pyo3(get)]
     pub(crate) hash: u64,
+    #[pyo3(get)]
+    pub(crate) is_exported: bool,
+    pub(crate) block_hashes: Vec<u64>,
+    pub(crate) key_filter: KeyFilter,
 }
 
 impl ControlFlowGraph {
     /// Creates a new `ControlFlowGraph`.
     pub fn new(name: &str, offset: u64, blocks: Vec<BasicBlock>) -> Self {
-        let mut hasher = StreamingChibiHasher::new(0x1337_u64);
-        for block in &blocks {
-            hasher.update(&block.hash.to_ne_bytes());
-        }
-        ControlFlowGraph {
+        ControlFlowGraph::with_normalization(name, offset, blocks, NormalizationMode::default())
+    }
+
+    /// Creates a new `ControlFlowGraph` whose blocks were normalized with the given `mode`.
+    pub(crate) fn with_normalization(
+        name: &str,
+        offset: u64,
+        blocks: Vec<BasicBlock>,
+        mode: NormalizationMode,
+    ) -> Self {
+        let mut graph = ControlFlowGraph {
             blocks,
-            hash: hasher.finalize(),
+            hash: 0,
             name: name.to_owned(),
             offset,
+            is_exported: false,
+            block_hashes: Vec::new(),
+            key_filter: KeyFilter::default(),
+        };
+        graph.rehash(mode.min_block_instructions);
+        graph
+    }
+
+    /// Re-compute the hash of the graph, its sorted block hashes and its key filter from its blocks.
+    ///
+    /// Blocks with fewer than `min_block_instructions` instructions are left out of the graph hash,
+    /// unless no block reaches it, in which case every block is hashed.
+    fn rehash(&mut self, min_block_instructions: usize) {
+        let hashes_all: bool = self
+            .blocks
+            .iter()
+            .all(|block| block.instructions.len() < min_block_instructions);
+        let mut hasher = StreamingChibiHasher::new(0x1337_u64);
+        for block in &self.blocks {
+            if hashes_all || block.instructions.len() >= min_block_instructions {
+                hasher.update(&block.hash.to_ne_bytes());
+            }
+        }
+        self.hash = hasher.finalize();
+
+        self.block_hashes = self.blocks.iter().map(|block| block.hash).collect();
+        self.block_hashes.sort_unstable();
+
+        self.key_filter = KeyFilter::default();
+        for key in self.blocks.iter().flat_map(|block| &block.keys) {
+            self.key_filter.insert(key);
         }
     }
 
+    /// Re-compute the instruction keys and the hashes of the graph for the given `mode`.
+    pub(crate) fn normalize(&mut self, mode: NormalizationMode) {
+        for block in &mut self.blocks {
+            block.normalize(mode);
+        }
+        self.rehash(mode.min_block_instructions);
+    }
+
     /// Name of the function of the Control Flow Graph (CFG).
     #[inline]
     pub fn name(&self) -> &String {
@@ -103,9 +460,308 @@ impl ControlFlowGraph {
         &self.blocks
     }
 
+    /// Index of the entry block of the graph, `None` if the graph has no blocks.
+    ///
+    /// The block starting at the function's offset is preferred, otherwise the lowest offset block
+    /// without predecessors, and failing that the lowest offset block.
+    pub fn entry_block_index(&self) -> Option<usize> {
+        self.blocks
+            .iter()
+            .position(|block| block.offset == self.offset)
+            .or_else(|| self.blocks.iter().position(|block| block.in_refs.is_empty()))
+            .or_else(|| (!self.blocks.is_empty()).then_some(0))
+    }
+
+    /// Indices of the exit blocks of the graph (blocks without successors), in offset order.
+    pub fn exit_block_indices(&self) -> Vec<usize> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .filter(|(_, block)| block.out_refs.is_empty())
+            .map(|(index, _)| index)
+            .collect()
+    }
+
+    /// The adjacency list of the graph: each block index along with the indices of its successors.
+    pub fn to_adjacency_list(&self) -> Vec<(usize, Vec<usize>)> {
+        self.blocks
+            .iter()
+            .enumerate()
+            .map(|(index, block)| (index, block.out_refs.clone()))
+            .collect()
+    }
+
+    /// The attributes of each block, by index: its offset and instruction count.
+    pub fn block_attributes(&self) -> Vec<(u64, usize)> {
+        self.blocks
+            .iter()
+            .map(|block| (block.offset, block.instructions.len()))
+            .collect()
+    }
+
+    /// Check that the graph's edges are well-formed, such as after manipulating its disassembly.
+    ///
+    /// Every `out_refs` and `in_refs` index must be within the graph's blocks and edges must be
+    /// symmetric, a block listing a successor being listed among its successor's predecessors and
+    /// conversely. Returns a description of the first inconsistency found.
+    pub fn validate(&self) -> Result<(), String> {
+        let block = |index: usize, kind: &str, from: usize| {
+            self.blocks.get(index).ok_or_else(|| {
+                format!(
+                    "block {from} ({:#x}) has out of bounds {kind} {index}, the graph has {} blocks",
+                    self.blocks[from].offset,
+                    self.blocks.len()
+                )
+            })
+        };
+
+        for (index, current) in self.blocks.iter().enumerate() {
+            for &successor in &current.out_refs {
+                if !block(successor, "successor", index)?.in_refs.contains(&index) {
+                    return Err(format!("edge {index} -> {successor} missing from the in_refs of block {successor}"));
+                }
+            }
+            for &predecessor in &current.in_refs {
+                if !block(predecessor, "predecessor", index)?.out_refs.contains(&index) {
+                    return Err(format!("edge {predecessor} -> {index} missing from the out_refs of block {predecessor}"));
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// The number of instructions of each mnemonic across the graph's blocks.
+    ///
+    /// A cheap feature vector of the function, comparing histograms (such as by their cosine
+    /// similarity) approximates the graph similarity well enough to shortlist candidate pairs.
+    pub fn mnemonic_histogram(&self) -> HashMap<String, usize> {
+        let mut histogram: HashMap<String, usize> = HashMap::new();
+        for instruction in self.blocks.iter().flat_map(|block| &block.instructions) {
+            *histogram.entry(instruction.mnemonic.clone()).or_default() += 1;
+        }
+        histogram
+    }
+
+    /// Every instruction of the graph, flattened across its blocks in ascending offset order.
+    pub fn all_instructions(&self) -> Vec<&Instruction> {
+        let mut blocks: Vec<&BasicBlock> = self.blocks.iter().collect();
+        blocks.sort_by_key(|block| block.offset);
+        blocks.into_iter().flat_map(|block| &block.instructions).collect()
+    }
+
+    /// The hashes of the graph's blocks in ascending order, duplicates included.
+    #[inline]
+    pub fn block_hashes(&self) -> &Vec<u64> {
+        &self.block_hashes
+    }
+
+    /// Whether both graphs are made of the same blocks, the exact counterpart of comparing hashes.
+    ///
+    /// The graph hash leaves out the blocks shorter than `min_block_instructions`, graphs differing
+    /// only in these blocks share their hash but not their block hashes.
+    pub fn is_identical(&self, other: &ControlFlowGraph) -> bool {
+        self.hash == other.hash && self.block_hashes == other.block_hashes
+    }
+
+    /// Number of blocks of both graphs with an identical hash, duplicates being matched once.
+    ///
+    /// Computed by merging the sorted block hashes in `O(l + r)`.
+    pub fn block_overlap(&self, other: &ControlFlowGraph) -> usize {
+        let (lhs, rhs) = (&self.block_hashes, &other.block_hashes);
+        let (mut l_index, mut r_index, mut overlap) = (0, 0, 0);
+        while l_index < lhs.len() && r_index < rhs.len() {
+            match lhs[l_index].cmp(&rhs[r_index]) {
+                std::cmp::Ordering::Less => l_index += 1,
+                std::cmp::Ordering::Greater => r_index += 1,
+                std::cmp::Ordering::Equal => {
+                    overlap += 1;
+                    l_index += 1;
+                    r_index += 1;
+                }
+            }
+        }
+        overlap
+    }
+
+    /// Align the blocks of the graph, as a sample method, one to one with those of a reference method.
+    ///
+    /// Block pairs are scored by the share of normalized instruction keys they have in common over
+    /// the larger block, and aligned greedily from the most similar pair. Blocks without any key in
+    /// common with a remaining block are reported as only existing on their side. Both graphs should
+    /// be normalized with the same mode.
+    pub fn diff_blocks(&self, reference: &ControlFlowGraph) -> BlockAlignment {
+        let mut pairs: Vec<(usize, usize, f32)> = Vec::new();
+        for (l_index, l_block) in self.blocks.iter().enumerate() {
+            for (r_index, r_block) in reference.blocks.iter().enumerate() {
+                let similarity: f32 = l_block.key_similarity(r_block);
+                if similarity > 0.0 {
+                    pairs.push((l_index, r_index, similarity));
+                }
+            }
+        }
+        // Stable sort, ties are aligned in block order.
+        pairs.sort_by(|lhs, rhs| rhs.2.total_cmp(&lhs.2));
+
+        let mut l_aligned: Vec<bool> = vec![false; self.blocks.len()];
+        let mut r_aligned: Vec<bool> = vec![false; reference.blocks.len()];
+        let mut matched: Vec<(u64, u64, f32)> = Vec::new();
+        for (l_index, r_index, similarity) in pairs {
+            if l_aligned[l_index] || r_aligned[r_index] {
+                continue;
+            }
+            l_aligned[l_index] = true;
+            r_aligned[r_index] = true;
+            matched.push((self.blocks[l_index].offset, reference.blocks[r_index].offset, similarity));
+        }
+        matched.sort_by_key(|(offset, _, _)| *offset);
+
+        fn unaligned(blocks: &[BasicBlock], aligned: Vec<bool>) -> Vec<u64> {
+            blocks.iter().zip(aligned).filter(|(_, aligned)| !aligned).map(|(block, _)| block.offset).collect()
+        }
+
+        BlockAlignment::new(
+            &self.name,
+            &reference.name,
+            matched,
+            unaligned(&self.blocks, l_aligned),
+            unaligned(&reference.blocks, r_aligned),
+        )
+    }
+
+    /// Whether the function's symbol is global / exported.
+    #[inline]
+    pub fn is_exported(&self) -> bool {
+        self.is_exported
+    }
+
     /// Non-Cryptographic hash of the graph's blocks.
+    ///
+    /// The hash depends on the `NormalizationMode` the graph was built with, including the blocks
+    /// it leaves out through `min_block_instructions`.
     #[inline]
     pub fn hash(&self) -> u64 {
         self.hash
     }
 }
+
+#[pymethods]
+impl ControlFlowGraph {
+    fn __repr__(&self) -> String {
+        format!("ControlFlowGraph(name='{}', offset={:#x}, blocks={})", self.name, self.offset, self.blocks.len())
+    }
+
+    #[pyo3(name = "entry_block_index")]
+    fn py_entry_block_index(&self) -> Option<usize> {
+        self.entry_block_index()
+    }
+
+    #[pyo3(name = "all_instructions")]
+    fn py_all_instructions(&self) -> Vec<String> {
+        self.all_instructions().into_iter().map(instruction_text).collect()
+    }
+
+    #[pyo3(name = "exit_block_indices")]
+    fn py_exit_block_indices(&self) -> Vec<usize> {
+        self.exit_block_indices()
+    }
+
+    #[pyo3(name = "to_adjacency_list")]
+    fn py_to_adjacency_list(&self) -> Vec<(usize, Vec<usize>)> {
+        self.to_adjacency_list()
+    }
+
+    #[pyo3(name = "block_attributes")]
+    fn py_block_attributes(&self) -> Vec<(u64, usize)> {
+        self.block_attributes()
+    }
+
+    #[pyo3(name = "validate")]
+    fn py_validate(&self) -> PyResult<()> {
+        self.validate().map_err(PyValueError::new_err)
+    }
+
+    #[pyo3(name = "mnemonic_histogram")]
+    fn py_mnemonic_histogram(&self) -> HashMap<String, usize> {
+        self.mnemonic_histogram()
+    }
+
+    #[pyo3(name = "diff_blocks")]
+    fn py_diff_blocks(&self, reference: PyRef<ControlFlowGraph>) -> BlockAlignment {
+        self.diff_blocks(&reference)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{block_with_mode, instruction};
+
+    #[test]
+    fn is_padding_matches_nops_and_int3() {
+        assert!(is_padding(&instruction(0x0, "90", "nop", "")));
+        assert!(is_padding(&instruction(0x0, "660f1f440000", "nopw", "word ptr [rax + rax]")));
+        assert!(is_padding(&instruction(0x0, "0f1f4000", "nopl", "dword ptr [rax]")));
+        assert!(is_padding(&instruction(0x0, "d9d0", "fnop", "")));
+        assert!(is_padding(&instruction(0x0, "cc", "int3", "")));
+        assert!(!is_padding(&instruction(0x0, "c3", "ret", "")));
+        assert!(!is_padding(&instruction(0x0, "f30f1efa", "endbr64", "")));
+    }
+
+    #[test]
+    fn strip_nops_ignores_padding_differences() {
+        let aligned: &[(&str, &str, &str)] = &[
+            ("55", "push", "rbp"),
+            ("660f1f440000", "nopw", "word ptr [rax + rax]"),
+            ("31c0", "xor", "eax, eax"),
+            ("c3", "ret", ""),
+        ];
+        let padded: &[(&str, &str, &str)] = &[
+            ("55", "push", "rbp"),
+            ("90", "nop", ""),
+            ("31c0", "xor", "eax, eax"),
+            ("c3", "ret", ""),
+            ("cc", "int3", ""),
+        ];
+        let blocks = |mode: NormalizationMode| {
+            (block_with_mode(0x1000, aligned, (&[], &[]), mode), block_with_mode(0x2000, padded, (&[], &[]), mode))
+        };
+
+        let (lhs, rhs) = blocks(NormalizationMode::default());
+        assert_ne!(lhs.keys, rhs.keys);
+        assert_ne!(lhs.hash, rhs.hash);
+
+        let (lhs, rhs) = blocks(NormalizationMode { strip_nops: true, ..Default::default() });
+        assert_eq!(lhs.keys, ["55", "31c0", "c3"]);
+        assert_eq!(lhs.keys, rhs.keys);
+        assert_eq!(lhs.hash, rhs.hash);
+    }
+
+    // The masked bytes of an instruction at `offset`.
+    fn masked(offset: u64, bytes: &str, mnemonic: &str, operands: &str) -> String {
+        mask_immediates(&instruction(offset, bytes, mnemonic, operands))
+    }
+
+    #[test]
+    fn mask_immediates_zeroes_immediates_and_displacements() {
+        assert_eq!(masked(0x0, "b878563412", "mov", "eax, 0x12345678"), "b800000000");
+        assert_eq!(masked(0x0, "48b88877665544332211", "movabs", "rax, 0x1122334455667788"), "48b80000000000000000");
+        // Negative displacements are located through their two's complement encoding.
+        assert_eq!(masked(0x0, "488b45e8", "mov", "rax, qword ptr [rbp - 0x18]"), "488b4500");
+        assert_eq!(masked(0x0, "488b8580feffff", "mov", "rax, qword ptr [rbp - 0x180]"), "488b8500000000");
+        // Decimal literals, such as small stack adjustments, are left untouched.
+        assert_eq!(masked(0x0, "4883c408", "add", "rsp, 8"), "4883c408");
+        assert_eq!(masked(0x0, "c3", "ret", ""), "c3");
+    }
+
+    #[test]
+    fn mask_immediates_zeroes_relative_branch_targets() {
+        // Branch operands are absolute targets, encoded relative to the end of the instruction.
+        assert_eq!(masked(0x1000, "7410", "je", "0x1012"), "7400");
+        assert_eq!(masked(0x1000, "ebfe", "jmp", "0x1000"), "eb00");
+        assert_eq!(masked(0x1000, "e9fb0f0000", "jmp", "0x2000"), "e900000000");
+        assert_eq!(masked(0x2000, "e8fbefffff", "call", "0x1000"), "e800000000");
+        assert_eq!(masked(0x1000, "0f84fa0f0000", "je", "0x2000"), "0f8400000000");
+        // Differently placed copies of the same branch share their masked bytes.
+        assert_eq!(masked(0x3000, "e8fbdfffff", "call", "0x1000"), masked(0x2000, "e8fbefffff", "call", "0x1000"));
+    }
+}
@@ -1,27 +1,60 @@
 use std::{
     borrow::Borrow,
-    collections::HashMap,
+    collections::{HashMap, HashSet},
+    ops::Deref,
+    panic,
     path::{Path, PathBuf},
     thread,
-    time::Duration
+    time::{Duration, Instant}
 };
 
-use object::{File, Object, ObjectSymbol, Symbol};
+use chibihash::StreamingChibiHasher;
+use object::{Architecture, BinaryFormat, File, Object, ObjectSymbol, Symbol};
 use pyo3::{
     pyclass,
     pymethods,
+    Py,
     PyRef,
     PyResult,
     Python,
-    exceptions::PyKeyboardInterrupt};
+    exceptions::{PyIndexError, PyKeyboardInterrupt}};
 use rand::seq::index::{sample, IndexVec};
+use rayon::prelude::*;
 use regex::Regex;
-use smda::{function::Instruction, report::DisassemblyReport, Disassembler};
+use smda::{function::{Function, Instruction}, report::DisassemblyReport, Disassembler};
 
-use crate::{control_flow_graph::{BasicBlock, ControlFlowGraph}, error::Error};
+use crate::{control_flow_graph::{BasicBlock, ControlFlowGraph, NormalizationMode}, error::Error};
+
+/// Options of smda's analysis of a binary, trading accuracy for speed.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct DisasmOptions {
+    /// Track the function candidates discovered while analysing each function, so that functions
+    /// only reached through them are found. Disabling it speeds up huge binaries but may miss
+    /// functions, leaving their matches out of the reports.
+    pub high_accuracy: bool,
+    /// Re-analyse the jumps to other functions as tail calls, splitting the functions they were
+    /// merged into. Disabling it saves a pass over the binary, at the cost of graphs spanning
+    /// several functions.
+    pub resolve_tailcalls: bool,
+    /// Convert the disassembled functions to graphs on all cores. Off by default, the conversion is
+    /// short next to smda's analysis and the thread pool overhead outweighs it on small binaries.
+    pub parallel_graphs: bool,
+}
+
+impl Default for DisasmOptions {
+    fn default() -> Self {
+        Self {
+            high_accuracy: true,
+            resolve_tailcalls: true,
+            parallel_graphs: false,
+        }
+    }
+}
 
 /// Data Model of a disassembled binary.
-#[pyclass]
+///
+/// Immutable once created, so that Python handles can be shared with the comparison threads.
+#[pyclass(frozen)]
 #[derive(Clone)]
 pub struct Disassembly {
     #[pyo3(get)]
@@ -30,62 +63,209 @@ pub struct Disassembly {
     pub(crate) path: PathBuf,
     #[pyo3(get)]
     pub(crate) graphs: Vec<ControlFlowGraph>,
+    #[pyo3(get)]
+    pub(crate) format: String,
+    #[pyo3(get)]
+    pub(crate) arch: String,
+    #[pyo3(get)]
+    pub(crate) bits: u8,
+    pub(crate) normalization: NormalizationMode,
+}
+
+/// Returns the short name of a binary format.
+fn format_name(format: BinaryFormat) -> String {
+    match format {
+        BinaryFormat::Elf => "elf".to_string(),
+        BinaryFormat::Pe => "pe".to_string(),
+        BinaryFormat::MachO => "macho".to_string(),
+        BinaryFormat::Coff => "coff".to_string(),
+        other => format!("{other:?}").to_lowercase(),
+    }
+}
+
+/// Returns the short name of an architecture.
+fn arch_name(architecture: Architecture) -> String {
+    match architecture {
+        Architecture::X86_64 | Architecture::X86_64_X32 => "amd64".to_string(),
+        Architecture::I386 => "i386".to_string(),
+        Architecture::Aarch64 | Architecture::Aarch64_Ilp32 => "arm64".to_string(),
+        Architecture::Arm => "arm".to_string(),
+        other => format!("{other:?}").to_lowercase(),
+    }
+}
+
+/// Returns the symbol name of a call instruction's target, if it can be resolved.
+fn call_target(instruction: &Instruction, symbols: &HashMap<u64, Symbol>) -> Option<String> {
+    if !instruction.mnemonic.starts_with("call") {
+        return None;
+    }
+    let operands: &str = instruction.operands.as_deref()?;
+    let target: u64 = u64::from_str_radix(operands.trim().strip_prefix("0x")?, 16).ok()?;
+    symbols
+        .get(&target)
+        .and_then(|symbol| symbol.name().ok())
+        .filter(|name| !name.is_empty())
+        .map(str::to_string)
 }
 
 impl Disassembly {
-    // TODO: Some of these `expects` should be returned as results...
+    /// Short names of the binary formats that can be disassembled, as reported by `format`.
+    ///
+    /// smda only disassembles ELF and PE binaries, other formats are rejected with
+    /// `Error::UnsupportedBinaryFormat`.
+    pub const SUPPORTED_FORMATS: &'static [&'static str] = &["elf", "pe"];
+
     /// Generate the set of Control Flow Graphs (CFG) for the specified binary.
     pub fn new(sample_path: &Path) -> Result<Self, Error> {
+        Disassembly::with_normalization(sample_path, NormalizationMode::default())
+    }
+
+    /// Generate the set of Control Flow Graphs (CFG) for the specified binary, normalizing its
+    /// instructions with the given `normalization` mode.
+    pub fn with_normalization(sample_path: &Path, normalization: NormalizationMode) -> Result<Self, Error> {
+        Disassembly::with_options(sample_path, normalization, DisasmOptions::default())
+    }
+
+    /// Generate the set of Control Flow Graphs (CFG) for the specified binary, analysing it with the
+    /// given smda `options`, see `DisasmOptions`.
+    pub fn with_options(
+        sample_path: &Path,
+        normalization: NormalizationMode,
+        options: DisasmOptions,
+    ) -> Result<Self, Error> {
+        Disassembly::disassemble(sample_path, normalization, options, None)
+    }
+
+    /// Generate the Control Flow Graphs (CFG) of the specified binary whose name match the supplied regex.
+    ///
+    /// smda always disassembles the whole binary, this does not save disassembly time. It only
+    /// skips building the graphs of the other functions, saving memory and graph construction time
+    /// compared to filtering the full disassembly afterwards.
+    pub fn new_filtered(sample_path: &Path, search_expression: &str) -> Result<Self, Error> {
+        let regex_exp: Regex = Regex::new(search_expression).map_err(|error| Error::InvalidRegex {
+            pattern: search_expression.to_string(),
+            reason: error.to_string(),
+        })?;
+        Disassembly::disassemble(sample_path, NormalizationMode::default(), DisasmOptions::default(), Some(&regex_exp))
+    }
+
+    /// List the (address, name) of the symbols of the specified binary, sorted by address.
+    ///
+    /// Only parses the binary's symbol table, without disassembling it. Symbols without a name are
+    /// left out.
+    pub fn list_symbols(sample_path: &Path) -> Result<Vec<(u64, String)>, Error> {
+        let sample_data: Vec<u8> = std::fs::read(sample_path).map_err(|error| Error::FileAccess {
+            path: sample_path.to_string_lossy().to_string(),
+            reason: error.to_string(),
+        })?;
+        let parsed_sample: File = File::parse(&*sample_data).map_err(|_| Error::UnsupportedBinaryFormat {
+            sample: sample_path.to_string_lossy().to_string(),
+        })?;
+
+        let mut symbols: Vec<(u64, String)> = parsed_sample
+            .symbols()
+            .filter_map(|symbol| {
+                let name: &str = symbol.name().ok().filter(|name| !name.is_empty())?;
+                Some((symbol.address(), name.to_string()))
+            })
+            .collect();
+        symbols.sort_unstable();
+        Ok(symbols)
+    }
+
+    // Disassemble the binary, only building the graphs of the functions whose name match `filter`.
+    fn disassemble(
+        sample_path: &Path,
+        normalization: NormalizationMode,
+        options: DisasmOptions,
+        filter: Option<&Regex>,
+    ) -> Result<Self, Error> {
+        let _span = tracing::debug_span!("disassemble", sample = %sample_path.display()).entered();
         let file_name = sample_path
             .file_name()
-            .expect("Sample has no file name")
+            .unwrap_or(sample_path.as_os_str())
             .to_string_lossy();
-        let sample_data = std::fs::read(sample_path).expect("Could not read sample data");
-        let parsed_sample = File::parse(&*sample_data).expect("Could not parse sample data");
+        let sample_data = std::fs::read(sample_path).map_err(|error| Error::FileAccess {
+            path: sample_path.to_string_lossy().to_string(),
+            reason: error.to_string(),
+        })?;
+        let disassembly_failed = |reason: String| Error::DisassemblyFailed {
+            sample: sample_path.to_string_lossy().to_string(),
+            reason,
+        };
+        let unsupported_format = || Error::UnsupportedBinaryFormat {
+            sample: sample_path.to_string_lossy().to_string(),
+        };
+        let parsed_sample = File::parse(&*sample_data).map_err(|_| unsupported_format())?;
+        if !Disassembly::SUPPORTED_FORMATS.contains(&format_name(parsed_sample.format()).as_str()) {
+            tracing::debug!(format = ?parsed_sample.format(), "unsupported binary format");
+            return Err(unsupported_format());
+        }
         // Build the hashmap of the symbols for fast access.
         let mut graph_symbols: HashMap<u64, Symbol> = HashMap::new();
         for symbol in parsed_sample.symbols() {
             graph_symbols.insert(symbol.address(), symbol);
         }
 
-        let sample_dissassembly_result: Result<DisassemblyReport, smda::Error> = Disassembler::disassemble_file(
-            &sample_path.to_string_lossy(),
-            true,
-            true,
-            Some(&sample_data),
-        );
-        
+        // smda may panic on malformed inputs, contain it to this sample.
+        let sample_dissassembly_result: Result<DisassemblyReport, smda::Error> = panic::catch_unwind(|| {
+            Disassembler::disassemble_file(
+                &sample_path.to_string_lossy(),
+                options.high_accuracy,
+                options.resolve_tailcalls,
+                Some(&sample_data),
+            )
+        }).map_err(|_| Error::DisassemblyPanicked {
+            sample: sample_path.to_string_lossy().to_string(),
+        })?;
+
         match sample_dissassembly_result {
             Err(error) => match error {
                 smda::Error::UnsupportedFormatError => {
-                    Err(Error::UnsupportedBinaryFormat {
-                        sample: sample_path.to_string_lossy().to_string(),
-                    })
+                    tracing::debug!("unsupported binary format");
+                    Err(unsupported_format())
                 },
-                _ => panic!("Failed to disassemble sample"),
+                error => Err(disassembly_failed(error.to_string())),
             },
             Ok(sample_dissassembly) => {
                 // Convert each smda_function to a ControlFlowGraph.
                 let smda_functions = sample_dissassembly
                     .get_functions()
-                    .expect("Failed to get functions");
-
-                let mut graphs: Vec<ControlFlowGraph> = Vec::with_capacity(smda_functions.len());
-                for (fct_offset, function) in smda_functions {
-                    let symbol_name: &str = if graph_symbols.contains_key(fct_offset) {
-                        graph_symbols[fct_offset]
-                            .name()
-                            .expect("Failed to get symbol name")
-                    } else {
-                        ""
-                    };
+                    .map_err(|error| disassembly_failed(error.to_string()))?;
+
+                // Functions are converted independently, in parallel if `parallel_graphs` is set.
+                let graphs_start: Instant = Instant::now();
+                let build_graph = |(fct_offset, function): (&u64, &Function)| {
+                    // Symbols whose name can not be decoded are treated as unnamed.
+                    let symbol_name: &str = graph_symbols
+                        .get(fct_offset)
+                        .and_then(|symbol| symbol.name().ok())
+                        .unwrap_or("");
+                    if filter.is_some_and(|regex_exp| !regex_exp.is_match(symbol_name)) {
+                        tracing::trace!(function = symbol_name, offset = fct_offset, "function filtered out");
+                        return None;
+                    }
+                    let is_exported: bool = graph_symbols
+                        .get(fct_offset)
+                        .is_some_and(|symbol| symbol.is_global());
 
                     // Convert each smda_block to a basic block.
                     let mut blocks: Vec<BasicBlock> = Vec::new();
-                    let smda_blocks: &HashMap<u64, Vec<Instruction>> =
-                        function.get_blocks().expect("Failed to get blocks");
+                    let smda_blocks: &HashMap<u64, Vec<Instruction>> = match function.get_blocks() {
+                        Ok(smda_blocks) => smda_blocks,
+                        Err(error) => return Some(Err(disassembly_failed(error.to_string()))),
+                    };
                     for (block_offset, instructions) in smda_blocks {
-                        let block = BasicBlock::new(*block_offset, instructions);
+                        let call_targets: Vec<Option<String>> = instructions
+                            .iter()
+                            .map(|ins| call_target(ins, &graph_symbols))
+                            .collect();
+                        let block = BasicBlock::with_call_targets(
+                            *block_offset,
+                            instructions,
+                            call_targets,
+                            normalization,
+                        );
                         blocks.push(block);
                     }
                     blocks.sort_by_key(|a| a.offset);
@@ -97,29 +277,54 @@ impl Disassembly {
                     }
 
                     // Resolve the incomming and outgoing edges.
+                    let block_ref = |offset: &u64| {
+                        block_indices.get(offset).copied().ok_or_else(|| {
+                            disassembly_failed(format!("invalid block ref {offset:#x} in function {fct_offset:#x}"))
+                        })
+                    };
                     for (offset, out_refs) in &function.blockrefs {
-                        let block_index: usize = *block_indices
-                            .get(offset)
-                            .expect("Failed to get block for offset");
+                        let block_index: usize = match block_ref(offset) {
+                            Ok(block_index) => block_index,
+                            Err(error) => return Some(Err(error)),
+                        };
 
                         for out_ref in out_refs {
-                            let out_index: usize = *block_indices.get(out_ref).expect("Invalid block ref");
+                            let out_index: usize = match block_ref(out_ref) {
+                                Ok(out_index) => out_index,
+                                Err(error) => return Some(Err(error)),
+                            };
                             blocks[block_index].out_refs.push(out_index);
                             blocks[out_index].in_refs.push(block_index);
                         }
                     }
                     // Sorts the block list by offsets.
-                    let graph = ControlFlowGraph::new(symbol_name, *fct_offset, blocks);
-                    graphs.push(graph);
-                }
+                    let mut graph =
+                        ControlFlowGraph::with_normalization(symbol_name, *fct_offset, blocks, normalization);
+                    graph.is_exported = is_exported;
+                    Some(Ok(graph))
+                };
+                let mut graphs: Vec<ControlFlowGraph> = if options.parallel_graphs {
+                    smda_functions.par_iter().filter_map(build_graph).collect::<Result<_, Error>>()?
+                } else {
+                    smda_functions.iter().filter_map(build_graph).collect::<Result<_, Error>>()?
+                };
 
                 // Sorts the final list by offsets.
                 graphs.sort_by_key(|a| a.offset);
+                tracing::debug!(
+                    functions = graphs.len(),
+                    graphs_secs = graphs_start.elapsed().as_secs_f64(),
+                    "sample disassembled",
+                );
 
                 Ok(Disassembly {
                     name: file_name.to_string(),
                     path: sample_path.to_path_buf(),
                     graphs,
+                    format: format_name(parsed_sample.format()),
+                    arch: arch_name(parsed_sample.architecture()),
+                    bits: if parsed_sample.is_64() { 64 } else { 32 },
+                    normalization,
                 })
             },
         }
@@ -143,11 +348,101 @@ impl Disassembly {
         &self.graphs
     }
 
+    /// Binary format of the disassembled binary (eg: "pe", "elf").
+    #[inline]
+    pub fn format(&self) -> &String {
+        &self.format
+    }
+
+    /// Architecture of the disassembled binary (eg: "amd64", "arm64").
+    #[inline]
+    pub fn arch(&self) -> &String {
+        &self.arch
+    }
+
+    /// Bitness of the disassembled binary.
+    #[inline]
+    pub fn bits(&self) -> u8 {
+        self.bits
+    }
+
+    /// The normalization mode the graphs' instructions were hashed with.
+    #[inline]
+    pub fn normalization(&self) -> NormalizationMode {
+        self.normalization
+    }
+
+    /// Non-Cryptographic hash of the whole binary, folded from the hashes of its graphs.
+    ///
+    /// The graph hashes are sorted before being folded, so that the fingerprint does not depend on
+    /// the graphs' order or offsets. Like the graph hashes, it depends on the normalization mode.
+    pub fn fingerprint(&self) -> u64 {
+        let mut hashes: Vec<u64> = self.graphs.iter().map(|graph| graph.hash).collect();
+        hashes.sort_unstable();
+
+        let mut hasher: StreamingChibiHasher = StreamingChibiHasher::new(0x1337_u64);
+        for hash in hashes {
+            hasher.update(&hash.to_ne_bytes());
+        }
+        hasher.finalize()
+    }
+
+    /// Returns a copy of the disassembly with its graphs re-normalized with the given `mode`.
+    pub fn normalize(&self, normalization: NormalizationMode) -> Self {
+        let mut disassembly: Disassembly = self.clone();
+        for graph in &mut disassembly.graphs {
+            graph.normalize(normalization);
+        }
+        disassembly.normalization = normalization;
+        disassembly
+    }
+
+    /// Combine several disassemblies, such as the object files of a library, into a single one.
+    ///
+    /// Graphs are concatenated in offset order, graphs overlapping an offset already present in an
+    /// earlier part are dropped. The path, format and normalization are taken from the first part,
+    /// the later parts being re-normalized to its mode if needed.
+    pub fn merge(name: &str, parts: Vec<Disassembly>) -> Self {
+        let mut parts = parts.into_iter();
+        let Some(first) = parts.next() else {
+            return Self {
+                name: name.to_string(),
+                path: PathBuf::new(),
+                graphs: Vec::new(),
+                format: String::new(),
+                arch: String::new(),
+                bits: 0,
+                normalization: NormalizationMode::default(),
+            };
+        };
+
+        let mut merged: Disassembly = Self {
+            name: name.to_string(),
+            ..first
+        };
+        let mut offsets: HashSet<u64> = merged.graphs.iter().map(|graph| graph.offset).collect();
+        for part in parts {
+            let part: Disassembly = if part.normalization == merged.normalization {
+                part
+            } else {
+                part.normalize(merged.normalization)
+            };
+            merged
+                .graphs
+                .extend(part.graphs.into_iter().filter(|graph| offsets.insert(graph.offset)));
+        }
+        merged.graphs.sort_by_key(|graph| graph.offset);
+        merged
+    }
+
     /// Returns a new Disassembly composed of the Control Flow Graphs (CFG) whose name match the supplied regex.
-    pub fn filter_symbol(&self, search_expression: &str) -> Self {
-        let regex_exp: Regex = Regex::new(search_expression).expect("Failed to create regex");
+    pub fn filter_symbol(&self, search_expression: &str) -> Result<Self, Error> {
+        let regex_exp: Regex = Regex::new(search_expression).map_err(|error| Error::InvalidRegex {
+            pattern: search_expression.to_string(),
+            reason: error.to_string(),
+        })?;
 
-        Self {
+        Ok(Self {
             name: self.name.clone(),
             path: self.path.clone(),
             graphs: self
@@ -156,6 +451,105 @@ impl Disassembly {
                 .filter(|&graph| regex_exp.is_match(&graph.name))
                 .cloned()
                 .collect(),
+            format: self.format.clone(),
+            arch: self.arch.clone(),
+            bits: self.bits,
+            normalization: self.normalization,
+        })
+    }
+
+    /// Returns a new Disassembly whose graph and block offsets are shifted by `delta`.
+    ///
+    /// Aligns the offsets with the addressing of another tool, such as a disassembler loading the
+    /// binary at an image base. The instructions keep their original addresses, hashes and
+    /// similarities are therefore unaffected.
+    pub fn rebase(&self, delta: i64) -> Self {
+        let mut rebased: Disassembly = self.clone();
+        for graph in &mut rebased.graphs {
+            graph.offset = graph.offset.wrapping_add_signed(delta);
+            for block in &mut graph.blocks {
+                block.offset = block.offset.wrapping_add_signed(delta);
+            }
+        }
+        rebased
+    }
+
+    /// Returns a new Disassembly composed of the Control Flow Graphs (CFG) whose symbol is global / exported.
+    pub fn exported_only(&self) -> Self {
+        Self {
+            name: self.name.clone(),
+            path: self.path.clone(),
+            graphs: self
+                .graphs
+                .iter()
+                .filter(|&graph| graph.is_exported)
+                .cloned()
+                .collect(),
+            format: self.format.clone(),
+            arch: self.arch.clone(),
+            bits: self.bits,
+            normalization: self.normalization,
+        }
+    }
+
+    /// Returns a new Disassembly composed of the `n` Control Flow Graphs (CFG) with the most instructions.
+    ///
+    /// Ties are broken by offset, lowest first, making the subset deterministic. The graphs are kept
+    /// in offset order.
+    pub fn top_functions(&self, n: usize) -> Self {
+        let instruction_count = |graph: &ControlFlowGraph| -> usize {
+            graph.blocks.iter().map(|block| block.instructions.len()).sum()
+        };
+
+        let mut graphs: Vec<&ControlFlowGraph> = self.graphs.iter().collect();
+        graphs.sort_by(|lhs, rhs| {
+            instruction_count(rhs)
+                .cmp(&instruction_count(lhs))
+                .then(lhs.offset.cmp(&rhs.offset))
+        });
+        graphs.truncate(n);
+        graphs.sort_by_key(|graph| graph.offset);
+
+        Self {
+            name: self.name.clone(),
+            path: self.path.clone(),
+            graphs: graphs.into_iter().cloned().collect(),
+            format: self.format.clone(),
+            arch: self.arch.clone(),
+            bits: self.bits,
+            normalization: self.normalization,
+        }
+    }
+
+    /// Returns a new Disassembly keeping a single Control Flow Graph (CFG) per unique set of blocks.
+    ///
+    /// The lowest offset graph of each set is kept, the number of duplicates removed is the
+    /// difference between the two disassemblies' graph counts. Graphs are told apart by their
+    /// block hashes, see `ControlFlowGraph::is_identical`.
+    pub fn dedup_graphs(&self) -> Self {
+        let mut unique: HashMap<(u64, &[u64]), &ControlFlowGraph> = HashMap::with_capacity(self.graphs.len());
+        for graph in &self.graphs {
+            unique
+                .entry((graph.hash, graph.block_hashes.as_slice()))
+                .and_modify(|kept| {
+                    if graph.offset < kept.offset {
+                        *kept = graph;
+                    }
+                })
+                .or_insert(graph);
+        }
+
+        let mut graphs: Vec<ControlFlowGraph> = unique.into_values().cloned().collect();
+        graphs.sort_by_key(|graph| graph.offset);
+
+        Self {
+            name: self.name.clone(),
+            path: self.path.clone(),
+            graphs,
+            format: self.format.clone(),
+            arch: self.arch.clone(),
+            bits: self.bits,
+            normalization: self.normalization,
         }
     }
 
@@ -171,16 +565,57 @@ impl Disassembly {
                 .iter()
                 .map(|index| self.graphs[index].clone())
                 .collect(),
+            format: self.format.clone(),
+            arch: self.arch.clone(),
+            bits: self.bits,
+            normalization: self.normalization,
         }
     }
 }
 
 #[pymethods]
 impl Disassembly {
+    fn __repr__(&self) -> String {
+        format!("Disassembly(name='{}', functions={})", self.name, self.graphs.len())
+    }
+
+    fn __len__(&self) -> usize {
+        self.graphs.len()
+    }
+
+    fn __iter__(slf: PyRef<Self>) -> GraphIterator {
+        GraphIterator {
+            disassembly: slf.into(),
+            index: 0,
+        }
+    }
+
+    fn __getitem__(&self, index: isize) -> PyResult<ControlFlowGraph> {
+        // Negative indices count from the end, as with Python lists.
+        let position: Option<usize> = if index < 0 {
+            self.graphs.len().checked_sub(index.unsigned_abs())
+        } else {
+            Some(index as usize)
+        };
+
+        position
+            .and_then(|position| self.graphs.get(position))
+            .cloned()
+            .ok_or_else(|| PyIndexError::new_err("Disassembly index out of range."))
+    }
+
     #[new]
-    fn py_new(sample_path: PathBuf, py: Python) -> PyResult<Self> {
+    #[pyo3(signature = (sample_path, *, high_accuracy=true, resolve_tailcalls=true, parallel_graphs=false))]
+    fn py_new(
+        sample_path: PathBuf,
+        high_accuracy: bool,
+        resolve_tailcalls: bool,
+        parallel_graphs: bool,
+        py: Python,
+    ) -> PyResult<Self> {
+        let options: DisasmOptions = DisasmOptions { high_accuracy, resolve_tailcalls, parallel_graphs };
         let thread_handle: thread::JoinHandle<Result<Self, Error>> = thread::spawn(move || {
-            Disassembly::new(&sample_path)
+            Disassembly::with_options(&sample_path, NormalizationMode::default(), options)
         });
 
         loop {
@@ -192,13 +627,78 @@ impl Disassembly {
             if thread_handle.is_finished() {
                 break Ok(thread_handle.join().unwrap()?);
             }
-            thread::sleep(Duration::from_millis(1));
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
         }
     }
 
+    #[staticmethod]
+    #[pyo3(name = "new_filtered")]
+    fn new_filtered_py(sample_path: PathBuf, search_expression: String, py: Python) -> PyResult<Self> {
+        let thread_handle: thread::JoinHandle<Result<Self, Error>> = thread::spawn(move || {
+            Disassembly::new_filtered(&sample_path, &search_expression)
+        });
+
+        loop {
+            if py.check_signals().is_err() {
+                break Err(
+                    PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
+                );
+            }
+            if thread_handle.is_finished() {
+                break Ok(thread_handle.join().unwrap()?);
+            }
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
+        }
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "supported_formats")]
+    fn supported_formats_py() -> Vec<&'static str> {
+        Disassembly::SUPPORTED_FORMATS.to_vec()
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "list_symbols")]
+    fn list_symbols_py(sample_path: PathBuf) -> PyResult<Vec<(u64, String)>> {
+        Ok(Disassembly::list_symbols(&sample_path)?)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "merge")]
+    fn merge_py(name: &str, parts: Vec<PyRef<Disassembly>>) -> Self {
+        Disassembly::merge(name, parts.iter().map(|part| part.deref().clone()).collect())
+    }
+
+    #[pyo3(name = "fingerprint")]
+    fn fingerprint_py(&self) -> u64 {
+        self.fingerprint()
+    }
+
     #[pyo3(name = "filter_symbol")]
-    fn filter_symbol_py(&self, search_expression: String) -> Self {
-        self.filter_symbol(search_expression.as_str())
+    fn filter_symbol_py(&self, search_expression: String) -> PyResult<Self> {
+        Ok(self.filter_symbol(search_expression.as_str())?)
+    }
+
+    #[pyo3(name = "exported_only")]
+    fn exported_only_py(&self) -> Self {
+        self.exported_only()
+    }
+
+    #[pyo3(name = "rebase")]
+    fn rebase_py(&self, delta: i64) -> Self {
+        self.rebase(delta)
+    }
+
+    #[pyo3(name = "top_functions")]
+    fn top_functions_py(&self, n: usize) -> Self {
+        self.top_functions(n)
+    }
+
+    #[pyo3(name = "dedup_graphs")]
+    fn dedup_graphs_py(&self) -> (Self, usize) {
+        let deduplicated: Disassembly = self.dedup_graphs();
+        let removed: usize = self.graphs.len() - deduplicated.graphs.len();
+        (deduplicated, removed)
     }
 
     #[pyo3(name = "get_subset")]
@@ -207,8 +707,100 @@ impl Disassembly {
     }
 }
 
+/// Python iterator over the Control Flow Graphs (CFG) of a disassembly, copying them one at a time.
+#[pyclass]
+pub struct GraphIterator {
+    disassembly: Py<Disassembly>,
+    index: usize,
+}
+
+#[pymethods]
+impl GraphIterator {
+    fn __iter__(slf: PyRef<Self>) -> PyRef<Self> {
+        slf
+    }
+
+    fn __next__(&mut self, py: Python) -> Option<ControlFlowGraph> {
+        let graph: Option<ControlFlowGraph> = self.disassembly.borrow(py).graphs.get(self.index).cloned();
+        self.index += 1;
+        graph
+    }
+}
+
 impl Borrow<Disassembly> for PyRef<'_, Disassembly> {
     fn borrow(&self) -> &Disassembly {
         self
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use std::path::PathBuf;
+
+    use super::*;
+    use crate::test_utils::{chain_graph, disassembly, keyed_block};
+
+    // Disassemble `data` written to a temporary file named after `name`.
+    fn disassemble(name: &str, data: &[u8]) -> Result<Disassembly, Error> {
+        let path: PathBuf = std::env::temp_dir().join(format!("gographer-{name}-{}", std::process::id()));
+        std::fs::write(&path, data).unwrap();
+        let result: Result<Disassembly, Error> = Disassembly::new(&path);
+        std::fs::remove_file(&path).unwrap();
+        result
+    }
+
+    #[test]
+    fn malformed_samples_return_errors() {
+        // A DOS header whose PE header offset lies past the end of the file.
+        let mut truncated_pe: Vec<u8> = vec![0; 0x40];
+        truncated_pe[..2].copy_from_slice(b"MZ");
+        truncated_pe[0x3c..].copy_from_slice(&0x80_u32.to_le_bytes());
+        let result = disassemble("truncated-pe", &truncated_pe);
+        assert!(matches!(result, Err(Error::UnsupportedBinaryFormat { .. })), "{:?}", result.err());
+
+        // An ELF identification without the rest of its header.
+        let result = disassemble("truncated-elf", b"\x7fELF\x02\x01\x01\x00");
+        assert!(matches!(result, Err(Error::UnsupportedBinaryFormat { .. })), "{:?}", result.err());
+
+        // The leading page of a valid binary, its sections and segments being cut off.
+        let executable: Vec<u8> = std::fs::read(std::env::current_exe().unwrap()).unwrap();
+        assert!(disassemble("truncated-exe", &executable[..0x1000]).is_err());
+
+        let missing: PathBuf = std::env::temp_dir().join(format!("gographer-missing-{}", std::process::id()));
+        assert!(matches!(Disassembly::new(&missing), Err(Error::FileAccess { .. })));
+    }
+
+    #[test]
+    fn new_filtered_rejects_invalid_regexes() {
+        let result: Result<Disassembly, Error> = Disassembly::new_filtered(Path::new("unused"), "main.(");
+        assert!(matches!(result, Err(Error::InvalidRegex { pattern, .. }) if pattern == "main.("));
+    }
+
+    #[test]
+    fn filter_symbol_rejects_invalid_regexes() {
+        let graph: ControlFlowGraph = chain_graph("main.f", 0x1000, vec![keyed_block(0x1000, &["c3"])]);
+        let sample: Disassembly = disassembly("sample", vec![graph]);
+        let result: Result<Disassembly, Error> = sample.filter_symbol("main.(");
+        assert!(matches!(result, Err(Error::InvalidRegex { pattern, .. }) if pattern == "main.("));
+        assert_eq!(sample.filter_symbol("^main\\.").unwrap().graphs.len(), 1);
+    }
+
+    #[test]
+    fn dedup_graphs_keeps_graphs_differing_in_short_blocks() {
+        let graph = |name: &str, offset: u64, exit: &str| {
+            let blocks: Vec<BasicBlock> = vec![keyed_block(offset, &["55", "4889e5", "31c0"]), keyed_block(offset + 3, &[exit])];
+            chain_graph(name, offset, blocks)
+        };
+        let mode: NormalizationMode = NormalizationMode { min_block_instructions: 2, ..Default::default() };
+        let sample: Disassembly = disassembly(
+            "sample",
+            vec![graph("main.f", 0x1000, "c3"), graph("main.g", 0x2000, "0f0b"), graph("main.h", 0x3000, "c3")],
+        )
+        .normalize(mode);
+
+        // The short exit blocks are left out of the hashes, only the true duplicate is removed.
+        assert!(sample.graphs.iter().all(|graph| graph.hash == sample.graphs[0].hash));
+        let offsets: Vec<u64> = sample.dedup_graphs().graphs.iter().map(|graph| graph.offset).collect();
+        assert_eq!(offsets, [0x1000, 0x2000]);
+    }
+}
@@ -2,10 +2,54 @@ use std::fmt::Debug;
 
 use pyo3::{exceptions::PyException, pyclass, pymethods, PyErr};
 
+use crate::disassembly::Disassembly;
+
 #[derive(thiserror::Error, Debug)]
 pub enum Error {
-    #[error("ERROR: Unsupported binary format for sample {sample:?} !")]
+    #[error(
+        "ERROR: Unsupported binary format for sample {sample:?}, supported formats are: {} !",
+        Disassembly::SUPPORTED_FORMATS.join(", ")
+    )]
     UnsupportedBinaryFormat { sample: String },
+
+    #[error("ERROR: Disassembler panicked while processing sample {sample:?} !")]
+    DisassemblyPanicked { sample: String },
+
+    #[error("ERROR: Sample {sample:?} could not be disassembled: {reason} !")]
+    DisassemblyFailed { sample: String, reason: String },
+
+    #[error("ERROR: Sample {sample:?} could not be disassembled, no comparison was possible !")]
+    SampleNotFound { sample: String },
+
+    #[error("ERROR: Report belongs to sample {expected:?}, got results for sample {found:?} !")]
+    SampleNameMismatch { expected: String, found: String },
+
+    #[error("ERROR: Could not access file {path:?}: {reason} !")]
+    FileAccess { path: String, reason: String },
+
+    #[error("ERROR: Invalid signature database {path:?}: {reason} !")]
+    InvalidSignatureDb { path: String, reason: String },
+
+    #[error("ERROR: Invalid glob pattern {pattern:?}: {reason} !")]
+    InvalidGlob { pattern: String, reason: String },
+
+    #[error("ERROR: Invalid regex {pattern:?}: {reason} !")]
+    InvalidRegex { pattern: String, reason: String },
+
+    #[error("ERROR: Report schema version {found} is newer than the supported version {supported} !")]
+    UnsupportedReportVersion { found: u32, supported: u32 },
+
+    #[error("ERROR: No function at offset {offset:#x} in sample {sample:?} !")]
+    FunctionNotFound { sample: String, offset: u64 },
+
+    #[error("ERROR: Invalid similarity {similarity} between functions {reference:?} and {sample:?} !")]
+    InvalidSimilarity { reference: String, sample: String, similarity: f32 },
+
+    #[error("ERROR: Invalid report {path:?}: {reason} !")]
+    InvalidReport { path: String, reason: String },
+
+    #[error("ERROR: Invalid option {option:?}: {reason} !")]
+    InvalidOption { option: String, reason: String },
 }
 
 impl From<Error> for PyErr {
@@ -16,15 +60,73 @@ impl From<Error> for PyErr {
             Error::UnsupportedBinaryFormat { sample } => {
                 PyErr::new::<PyUnsupportedBinaryFormat, _>((message, sample))
             }
+            Error::DisassemblyPanicked { sample } => {
+                PyErr::new::<PyDisassemblyPanicked, _>((message, sample))
+            }
+            Error::DisassemblyFailed { sample, reason } => {
+                PyErr::new::<PyDisassemblyFailed, _>((message, sample, reason))
+            }
+            Error::SampleNotFound { sample } => {
+                PyErr::new::<PySampleNotFound, _>((message, sample))
+            }
+            Error::SampleNameMismatch { expected, found } => {
+                PyErr::new::<PySampleNameMismatch, _>((message, expected, found))
+            }
+            Error::FileAccess { path, reason } => {
+                PyErr::new::<PyFileAccessError, _>((message, path, reason))
+            }
+            Error::InvalidSignatureDb { path, reason } => {
+                PyErr::new::<PyInvalidSignatureDb, _>((message, path, reason))
+            }
+            Error::InvalidGlob { pattern, reason } => {
+                PyErr::new::<PyInvalidGlob, _>((message, pattern, reason))
+            }
+            Error::InvalidRegex { pattern, reason } => {
+                PyErr::new::<PyInvalidRegex, _>((message, pattern, reason))
+            }
+            Error::UnsupportedReportVersion { found, supported } => {
+                PyErr::new::<PyUnsupportedReportVersion, _>((message, found, supported))
+            }
+            Error::FunctionNotFound { sample, offset } => {
+                PyErr::new::<PyFunctionNotFound, _>((message, sample, offset))
+            }
+            Error::InvalidSimilarity { reference, sample, similarity } => {
+                PyErr::new::<PyInvalidSimilarity, _>((message, reference, sample, similarity))
+            }
+            Error::InvalidReport { path, reason } => {
+                PyErr::new::<PyInvalidReport, _>((message, path, reason))
+            }
+            Error::InvalidOption { option, reason } => {
+                PyErr::new::<PyInvalidOption, _>((message, option, reason))
+            }
         }
     }
 }
 
-/// Python version of the UnsupportedBinaryFormat error.
-#[pyclass(extends=PyException, name="UnsupportedBinaryFormat")]
-pub(super) struct PyUnsupportedBinaryFormat {
+/// Python base class of GoGrapher's errors, `except GoGrapherError` catching all of them.
+#[pyclass(extends=PyException, subclass, name="GoGrapherError")]
+pub(super) struct PyGoGrapherError {
     #[pyo3(get)]
     message: String,
+}
+
+#[pymethods]
+impl PyGoGrapherError {
+    /// Create a new PyGoGrapherError instance.
+    #[new]
+    fn new(message: String) -> Self {
+        Self { message }
+    }
+
+    /// Return the error message as its string representation.
+    fn __str__(&self) -> &String {
+        &self.message
+    }
+}
+
+/// Python version of the UnsupportedBinaryFormat error.
+#[pyclass(extends=PyGoGrapherError, name="UnsupportedBinaryFormat")]
+pub(super) struct PyUnsupportedBinaryFormat {
     #[pyo3(get)]
     sample: String,
 }
@@ -33,12 +135,239 @@ pub(super) struct PyUnsupportedBinaryFormat {
 impl PyUnsupportedBinaryFormat {
     /// Create a new PyUnsupportedBinaryFormat instance.
     #[new]
-    fn new(message: String, sample: String) -> Self {
-        Self { message, sample }
+    fn new(message: String, sample: String) -> (Self, PyGoGrapherError) {
+        (Self { sample }, PyGoGrapherError { message })
     }
+}
 
-    /// Return the error message as its string representation.
-    fn __str__(&self) -> &String {
-        &self.message
+/// Python version of the DisassemblyPanicked error.
+#[pyclass(extends=PyGoGrapherError, name="DisassemblyPanicked")]
+pub(super) struct PyDisassemblyPanicked {
+    #[pyo3(get)]
+    sample: String,
+}
+
+#[pymethods]
+impl PyDisassemblyPanicked {
+    /// Create a new PyDisassemblyPanicked instance.
+    #[new]
+    fn new(message: String, sample: String) -> (Self, PyGoGrapherError) {
+        (Self { sample }, PyGoGrapherError { message })
+    }
+}
+
+/// Python version of the DisassemblyFailed error.
+#[pyclass(extends=PyGoGrapherError, name="DisassemblyFailed")]
+pub(super) struct PyDisassemblyFailed {
+    #[pyo3(get)]
+    sample: String,
+    #[pyo3(get)]
+    reason: String,
+}
+
+#[pymethods]
+impl PyDisassemblyFailed {
+    /// Create a new PyDisassemblyFailed instance.
+    #[new]
+    fn new(message: String, sample: String, reason: String) -> (Self, PyGoGrapherError) {
+        (Self { sample, reason }, PyGoGrapherError { message })
+    }
+}
+
+/// Python version of the SampleNotFound error.
+#[pyclass(extends=PyGoGrapherError, name="SampleNotFound")]
+pub(super) struct PySampleNotFound {
+    #[pyo3(get)]
+    sample: String,
+}
+
+#[pymethods]
+impl PySampleNotFound {
+    /// Create a new PySampleNotFound instance.
+    #[new]
+    fn new(message: String, sample: String) -> (Self, PyGoGrapherError) {
+        (Self { sample }, PyGoGrapherError { message })
+    }
+}
+
+/// Python version of the SampleNameMismatch error.
+#[pyclass(extends=PyGoGrapherError, name="SampleNameMismatch")]
+pub(super) struct PySampleNameMismatch {
+    #[pyo3(get)]
+    expected: String,
+    #[pyo3(get)]
+    found: String,
+}
+
+#[pymethods]
+impl PySampleNameMismatch {
+    /// Create a new PySampleNameMismatch instance.
+    #[new]
+    fn new(message: String, expected: String, found: String) -> (Self, PyGoGrapherError) {
+        (Self { expected, found }, PyGoGrapherError { message })
+    }
+}
+
+/// Python version of the FileAccess error.
+#[pyclass(extends=PyGoGrapherError, name="FileAccessError")]
+pub(super) struct PyFileAccessError {
+    #[pyo3(get)]
+    path: String,
+    #[pyo3(get)]
+    reason: String,
+}
+
+#[pymethods]
+impl PyFileAccessError {
+    /// Create a new PyFileAccessError instance.
+    #[new]
+    fn new(message: String, path: String, reason: String) -> (Self, PyGoGrapherError) {
+        (Self { path, reason }, PyGoGrapherError { message })
+    }
+}
+
+/// Python version of the InvalidSignatureDb error.
+#[pyclass(extends=PyGoGrapherError, name="InvalidSignatureDb")]
+pub(super) struct PyInvalidSignatureDb {
+    #[pyo3(get)]
+    path: String,
+    #[pyo3(get)]
+    reason: String,
+}
+
+#[pymethods]
+impl PyInvalidSignatureDb {
+    /// Create a new PyInvalidSignatureDb instance.
+    #[new]
+    fn new(message: String, path: String, reason: String) -> (Self, PyGoGrapherError) {
+        (Self { path, reason }, PyGoGrapherError { message })
+    }
+}
+
+/// Python version of the InvalidGlob error.
+#[pyclass(extends=PyGoGrapherError, name="InvalidGlob")]
+pub(super) struct PyInvalidGlob {
+    #[pyo3(get)]
+    pattern: String,
+    #[pyo3(get)]
+    reason: String,
+}
+
+#[pymethods]
+impl PyInvalidGlob {
+    /// Create a new PyInvalidGlob instance.
+    #[new]
+    fn new(message: String, pattern: String, reason: String) -> (Self, PyGoGrapherError) {
+        (Self { pattern, reason }, PyGoGrapherError { message })
+    }
+}
+
+/// Python version of the InvalidRegex error.
+#[pyclass(extends=PyGoGrapherError, name="InvalidRegex")]
+pub(super) struct PyInvalidRegex {
+    #[pyo3(get)]
+    pattern: String,
+    #[pyo3(get)]
+    reason: String,
+}
+
+#[pymethods]
+impl PyInvalidRegex {
+    /// Create a new PyInvalidRegex instance.
+    #[new]
+    fn new(message: String, pattern: String, reason: String) -> (Self, PyGoGrapherError) {
+        (Self { pattern, reason }, PyGoGrapherError { message })
+    }
+}
+
+/// Python version of the UnsupportedReportVersion error.
+#[pyclass(extends=PyGoGrapherError, name="UnsupportedReportVersion")]
+pub(super) struct PyUnsupportedReportVersion {
+    #[pyo3(get)]
+    found: u32,
+    #[pyo3(get)]
+    supported: u32,
+}
+
+#[pymethods]
+impl PyUnsupportedReportVersion {
+    /// Create a new PyUnsupportedReportVersion instance.
+    #[new]
+    fn new(message: String, found: u32, supported: u32) -> (Self, PyGoGrapherError) {
+        (Self { found, supported }, PyGoGrapherError { message })
+    }
+}
+
+/// Python version of the FunctionNotFound error.
+#[pyclass(extends=PyGoGrapherError, name="FunctionNotFound")]
+pub(super) struct PyFunctionNotFound {
+    #[pyo3(get)]
+    sample: String,
+    #[pyo3(get)]
+    offset: u64,
+}
+
+#[pymethods]
+impl PyFunctionNotFound {
+    /// Create a new PyFunctionNotFound instance.
+    #[new]
+    fn new(message: String, sample: String, offset: u64) -> (Self, PyGoGrapherError) {
+        (Self { sample, offset }, PyGoGrapherError { message })
+    }
+}
+
+/// Python version of the InvalidSimilarity error.
+#[pyclass(extends=PyGoGrapherError, name="InvalidSimilarity")]
+pub(super) struct PyInvalidSimilarity {
+    #[pyo3(get)]
+    reference: String,
+    #[pyo3(get)]
+    sample: String,
+    #[pyo3(get)]
+    similarity: f32,
+}
+
+#[pymethods]
+impl PyInvalidSimilarity {
+    /// Create a new PyInvalidSimilarity instance.
+    #[new]
+    fn new(message: String, reference: String, sample: String, similarity: f32) -> (Self, PyGoGrapherError) {
+        (Self { reference, sample, similarity }, PyGoGrapherError { message })
+    }
+}
+
+/// Python version of the InvalidReport error.
+#[pyclass(extends=PyGoGrapherError, name="InvalidReport")]
+pub(super) struct PyInvalidReport {
+    #[pyo3(get)]
+    path: String,
+    #[pyo3(get)]
+    reason: String,
+}
+
+#[pymethods]
+impl PyInvalidReport {
+    /// Create a new PyInvalidReport instance.
+    #[new]
+    fn new(message: String, path: String, reason: String) -> (Self, PyGoGrapherError) {
+        (Self { path, reason }, PyGoGrapherError { message })
+    }
+}
+
+/// Python version of the InvalidOption error.
+#[pyclass(extends=PyGoGrapherError, name="InvalidOption")]
+pub(super) struct PyInvalidOption {
+    #[pyo3(get)]
+    option: String,
+    #[pyo3(get)]
+    reason: String,
+}
+
+#[pymethods]
+impl PyInvalidOption {
+    /// Create a new PyInvalidOption instance.
+    #[new]
+    fn new(message: String, option: String, reason: String) -> (Self, PyGoGrapherError) {
+        (Self { option, reason }, PyGoGrapherError { message })
     }
 }
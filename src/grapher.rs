@@ -1,28 +1,44 @@
 use std::{
-    borrow::Borrow,
+    borrow::{Borrow, Cow},
+    collections::{HashMap, HashSet},
+    num::NonZeroUsize,
     ops::Deref,
-    path::PathBuf,
-    sync::{Arc, Mutex},
+    path::{Path, PathBuf},
+    sync::{atomic::{AtomicBool, AtomicUsize, Ordering}, mpsc, Arc, Mutex},
     time::{Duration, Instant},
     thread
 };
 
+use dashmap::DashMap;
 use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
 use pyo3::{
     pyclass,
     pymethods,
+    Py,
+    PyAny,
+    PyErr,
     PyRef,
+    PyRefMut,
     PyResult,
     Python,
-    exceptions::PyKeyboardInterrupt
+    exceptions::{PyBaseException, PyKeyboardInterrupt, PyValueError}
 };
 use rayon::prelude::*;
-use smda::function::Instruction;
+use serde::{Deserialize, Serialize};
 
-use crate::{compare_report::CompareReport, error::Error};
-use crate::control_flow_graph::{BasicBlock, ControlFlowGraph};
+use crate::{compare_report::{CompareConfig, CompareReport}, error::Error};
+use crate::checkpoint::Checkpoint;
+use crate::control_flow_graph::{BasicBlock, ControlFlowGraph, NormalizationMode};
 use crate::disassembly::Disassembly;
+use crate::method_diff::{BlockDiff, MethodDiff};
 use crate::r#match::{Binary as BinaryMatch, Method as MethodMatch};
+use crate::signature_db::SignatureDb;
+
+/// Successful disassemblies along with the path and error of each failed sample.
+pub type LenientGraphs = (Vec<Disassembly>, Vec<(PathBuf, Error)>);
+
+// Python version of a failed sample, the path and the exception raised.
+type PyGraphFailure = (PathBuf, Py<PyBaseException>);
 
 struct InstructionStreamer<'a> {
     blocks: &'a [BasicBlock],
@@ -34,12 +50,13 @@ impl<'a> InstructionStreamer<'a> {
         Self { blocks, indices }
     }
 
+    // Number of keys yielded by `iter`, empty blocks contributing none.
     fn len(&self) -> usize {
-        let mut count = 0;
-        for i in self.indices {
-            count += self.blocks[*i].instructions.len()
-        }
-        count
+        self.indices
+            .iter()
+            .filter_map(|i| self.blocks.get(*i))
+            .map(|block| block.keys.len())
+            .sum()
     }
 
     fn iter(&self) -> InstructionStreamerIter<'_> {
@@ -52,370 +69,2384 @@ impl<'a> InstructionStreamer<'a> {
 }
 
 struct InstructionStreamerIter<'a> {
-    iter: Option<std::slice::Iter<'a, Instruction>>,
+    iter: Option<std::slice::Iter<'a, String>>,
     indices: std::slice::Iter<'a, usize>,
     streamer: &'a InstructionStreamer<'a>,
 }
 
 impl<'a> Iterator for InstructionStreamerIter<'a> {
-    type Item = &'a Instruction;
+    type Item = &'a String;
 
     fn next(&mut self) -> Option<Self::Item> {
-        if let Some(it) = self.iter.as_mut() {
-            let next = it.next();
-            if next.is_some() {
-                return next;
+        // Chain the blocks' keys, moving on to the next block whenever one is exhausted. Empty
+        // blocks are skipped rather than ending the stream, as are the blocks out of the slice,
+        // such as past the prefix compared with `prefix_blocks`.
+        loop {
+            if let Some(next) = self.iter.as_mut().and_then(|it| it.next()) {
+                return Some(next);
             }
+            let i: &usize = self.indices.next()?;
+            self.iter = self.streamer.blocks.get(*i).map(|block| block.keys.iter());
+        }
+    }
+}
+
+/// Strategy used to pool the best block similarities of a graph into the graph's similarity.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Pooling {
+    /// Average of the best `min(l, r)` block similarities.
+    #[default]
+    Mean,
+    /// The single best block similarity, surfaces subroutines embedded in larger functions.
+    Max,
+    /// Average of the best `K` block similarities.
+    TopK(NonZeroUsize),
+}
+
+impl Pooling {
+    // Number of the best block similarities of `l` blocks, compared against `r`, read by `pool`.
+    fn retained(&self, l: usize, r: usize) -> usize {
+        match self {
+            Pooling::Mean => std::cmp::min(l, r),
+            Pooling::Max => std::cmp::min(l, 1),
+            Pooling::TopK(k) => std::cmp::min(k.get(), l),
+        }
+    }
+
+    // Pool the (similarity, weight) of the blocks, sorted in descending order, into a single similarity.
+    fn pool(&self, top_sims: &[(f32, f32)], sample_size: usize) -> f32 {
+        match self {
+            Pooling::Mean => Pooling::weighted_mean(&top_sims[..sample_size]),
+            Pooling::Max => top_sims.first().map_or(0.0, |(similarity, _)| *similarity),
+            Pooling::TopK(k) => Pooling::weighted_mean(&top_sims[..std::cmp::min(k.get(), top_sims.len())]),
+        }
+    }
+
+    // Weighted mean of block similarities, uniform if none of the blocks has any weight.
+    fn weighted_mean(top_sims: &[(f32, f32)]) -> f32 {
+        let total_weight: f32 = top_sims.iter().map(|(_, weight)| weight).sum();
+        if total_weight == 0.0 {
+            return top_sims.iter().map(|(similarity, _)| similarity).sum::<f32>() / top_sims.len() as f32;
+        }
+        top_sims.iter().map(|(similarity, weight)| similarity * weight).sum::<f32>() / total_weight
+    }
+
+    // Sort (similarity, weight) pairs in descending order, heavier blocks first among equal similarities.
+    fn sort(top_sims: &mut [(f32, f32)]) {
+        top_sims.sort_unstable_by(|x, y| x.0.total_cmp(&y.0).then(x.1.total_cmp(&y.1)).reverse());
+    }
+}
+
+/// Weight of each block's similarity within the pooled similarity of its graph.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum BlockWeighting {
+    /// Every block weighs the same, a 2 instruction block counting as much as a 50 instruction one.
+    #[default]
+    Uniform,
+    /// Blocks are weighted by their normalized instruction count, large distinctive blocks dominate.
+    InstructionCount,
+}
+
+impl BlockWeighting {
+    // The weight of a block of the first graph of a comparison.
+    fn weight(&self, block: &BasicBlock) -> f32 {
+        match self {
+            BlockWeighting::Uniform => 1.0,
+            BlockWeighting::InstructionCount => block.keys.len() as f32,
+        }
+    }
+}
+
+/// How the blocks of two graphs are paired before their similarities are pooled.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Alignment {
+    /// Each block is paired with its most similar block, independently of the other blocks.
+    #[default]
+    Greedy,
+    /// Blocks are paired one to one by a maximum weight bipartite matching of their similarities.
+    ///
+    /// Yields a single consistent alignment, at the cost of an `O(n^3)` assignment per graph pair
+    /// on top of the block similarity matrix.
+    Hungarian,
+}
+
+/// Block count a graph similarity is normalized by, given the block counts of both graphs.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GraphNorm {
+    /// The smaller block count, a function contained within a larger one scores high.
+    #[default]
+    Min,
+    /// The larger block count, penalizing size differences for a strict equivalence metric.
+    Max,
+    /// The mean of both block counts.
+    Mean,
+}
+
+impl GraphNorm {
+    // The block count to normalize by, for graphs of `l` and `r` blocks.
+    fn size(&self, l: usize, r: usize) -> f32 {
+        match self {
+            GraphNorm::Min => std::cmp::min(l, r) as f32,
+            GraphNorm::Max => std::cmp::max(l, r) as f32,
+            GraphNorm::Mean => (l + r) as f32 / 2.0,
+        }
+    }
+}
+
+// Inverse document frequency of the instruction keys of a reference corpus.
+struct InstructionWeights {
+    weights: HashMap<String, f32>,
+    unseen: f32,
+}
+
+/// How the progress of long operations is reported.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ProgressMode {
+    /// No progress is reported.
+    None,
+    /// Interactive terminal progress bars and spinners.
+    #[default]
+    Bar,
+    /// Plain `progress: X/Y` lines printed to stderr, friendlier to log aggregation.
+    Log,
+}
+
+/// Templates and characters of the progress bars and spinners, see `indicatif::ProgressStyle`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ProgressTheme {
+    /// Template of the bar tracking the references compared to a sample.
+    pub overall_template: String,
+    /// Template of the bar tracking the functions of a reference compared to a sample.
+    pub reference_template: String,
+    /// Template of the spinners displayed while disassembling.
+    pub spinner_template: String,
+    /// Characters of the filled, current and empty parts of the bars.
+    pub progress_chars: String,
+    /// Frames of the spinners, the last one displayed once finished, indicatif's default if `None`.
+    pub tick_chars: Option<String>,
+}
+
+impl Default for ProgressTheme {
+    fn default() -> Self {
+        Self {
+            overall_template: "[{elapsed_precise} - {eta}] {msg:.green} [{wide_bar:.green/blue}] {pos}/{len} references".to_string(),
+            reference_template: "[{elapsed_precise} - {eta}] {msg:.yellow} [{wide_bar:.yellow/red}] {pos}/{len} ({percent} %)".to_string(),
+            spinner_template: "{spinner:.green} [{elapsed_precise}] {msg:.yellow}".to_string(),
+            progress_chars: "#>-".to_string(),
+            tick_chars: None,
         }
-        // NOTE: Incorrect linter warning...
-        #[allow(clippy::never_loop)]
-        for i in &mut self.indices {
-            let mut it = self.streamer.blocks[*i].instructions.iter();
-            let next = it.next();
-            self.iter = Some(it);
-            return next;
+    }
+}
+
+impl ProgressTheme {
+    /// Plain ASCII theme without colors, for consoles rendering unicode or ANSI colors poorly.
+    pub fn ascii() -> Self {
+        Self {
+            overall_template: "[{elapsed_precise} - {eta}] {msg} [{wide_bar}] {pos}/{len} references".to_string(),
+            reference_template: "[{elapsed_precise} - {eta}] {msg} [{wide_bar}] {pos}/{len} ({percent} %)".to_string(),
+            spinner_template: "{spinner} [{elapsed_precise}] {msg}".to_string(),
+            progress_chars: "#>-".to_string(),
+            tick_chars: Some("|/-\\ ".to_string()),
         }
-        None
     }
 }
 
+/// Default block count `k` of the method match confidence, see `Grapher::with_confidence_scale`.
+pub const DEFAULT_CONFIDENCE_SCALE: f32 = 4.0;
+
+/// Default tolerance of the similarity comparisons, see `Grapher::with_epsilon`.
+pub const DEFAULT_EPSILON: f32 = 1e-6;
+
 /// Compute a summary of the similarities between a malware sample and a set of clean libraries.
 #[pyclass]
 #[derive(Clone)]
 pub struct Grapher {
-    display_progress: bool,
+    progress_mode: ProgressMode,
+    progress_theme: ProgressTheme,
     multiprogress: Arc<Option<MultiProgress>>,
     threshold: f32,
+    normalization: NormalizationMode,
+    skip_self: bool,
+    pooling: Pooling,
+    idf: Option<Arc<InstructionWeights>>,
+    confidence_scale: f32,
+    cancelled: Arc<AtomicBool>,
+    block_alignment: bool,
+    fuzzy_bytes: bool,
+    fast_block_overlap: bool,
+    alignment: Alignment,
+    size_ratio_gate: Option<f32>,
+    checkpoint_path: Option<PathBuf>,
+    graph_norm: GraphNorm,
+    block_weighting: BlockWeighting,
+    strict: bool,
+    epsilon: f32,
+    report_floor: f32,
+    prefix_blocks: Option<NonZeroUsize>,
+    bloom_prefilter: Option<f32>,
+    max_reference_graphs: Option<usize>,
+    chunk_size: Option<NonZeroUsize>,
+    invalid_similarity: Arc<Mutex<Option<Error>>>,
+    similarity_cache: Option<Arc<DashMap<(u64, u64), f32>>>,
 }
 
 impl Grapher {
     /// Creates a new Grapher instance.
     ///
-    /// Where `threshold` is the value which when reached matches are considered significant.
+    /// Where `threshold` is the value which when reached matches are considered significant. It is
+    /// clamped to `[0, 1]`, the range of similarities, with a warning.
     pub fn new(threshold: f32, display_progress: bool) -> Self {
-        let mut multiprogress: Arc<Option<MultiProgress>> = Arc::new(None);
-        if display_progress {
-            multiprogress = Arc::new(Some(MultiProgress::new()));
+        let clamped: f32 = if threshold.is_nan() { 0.0 } else { threshold.clamp(0.0, 1.0) };
+        if clamped != threshold {
+            tracing::warn!(threshold, clamped, "threshold outside of [0, 1], clamped");
         }
+        let threshold: f32 = clamped;
+        let progress_mode: ProgressMode = if display_progress {
+            ProgressMode::Bar
+        } else {
+            ProgressMode::None
+        };
 
         Self {
-            display_progress,
-            multiprogress,
+            progress_mode: ProgressMode::None,
+            progress_theme: ProgressTheme::default(),
+            multiprogress: Arc::new(None),
             threshold,
+            normalization: NormalizationMode::default(),
+            skip_self: false,
+            pooling: Pooling::default(),
+            idf: None,
+            confidence_scale: DEFAULT_CONFIDENCE_SCALE,
+            cancelled: Arc::new(AtomicBool::new(false)),
+            block_alignment: false,
+            fuzzy_bytes: false,
+            fast_block_overlap: false,
+            alignment: Alignment::default(),
+            size_ratio_gate: None,
+            checkpoint_path: None,
+            graph_norm: GraphNorm::default(),
+            block_weighting: BlockWeighting::default(),
+            strict: false,
+            epsilon: DEFAULT_EPSILON,
+            report_floor: 0.0,
+            prefix_blocks: None,
+            bloom_prefilter: None,
+            max_reference_graphs: None,
+            chunk_size: None,
+            invalid_similarity: Arc::new(Mutex::new(None)),
+            similarity_cache: None,
         }
+        .with_progress_mode(progress_mode)
     }
 
-    /// Compare a malware sample to a clean set of libraries and produce a matching pairs reports.
+    /// Sets whether immediate and displacement bytes are ignored when comparing instructions.
     ///
-    /// The `sample_graph` is the Control Flow Graph (CFG) of the malware sample to compare and
-    /// `reference_graphs` is the list of reference Control Flow Graphs (CFG) to compare to.
-    pub fn compare<T: Sync + Borrow<Disassembly>>(
-        &self,
-        sample_graph: T,
-        reference_graphs: Vec<T>,
-    ) -> CompareReport {
-        let sample_graph_ref: &Disassembly = sample_graph.borrow();
-        let mut matches_list: Vec<BinaryMatch> = Vec::with_capacity(reference_graphs.len());
-        let compute_start: Instant = Instant::now();
+    /// Call targets and absolute addresses differ between builds even when the logic is identical,
+    /// masking them greatly improves the matching of functions across versions.
+    pub fn with_mask_immediates(mut self, mask_immediates: bool) -> Self {
+        self.normalization.mask_immediates = mask_immediates;
+        self
+    }
 
-        { // Compare each sample graph.
-            let matches_list: Arc<Mutex<&mut Vec<BinaryMatch>>> =
-                Arc::new(Mutex::new(&mut matches_list));
+    /// Sets whether the resolved symbol of a call's target is part of the call instruction's key.
+    ///
+    /// Calls to different functions can share the same bytes once relocated or masked, the target's
+    /// name tells them apart. Only calls whose target has a symbol in the binary are affected.
+    pub fn with_call_targets(mut self, use_call_targets: bool) -> Self {
+        self.normalization.use_call_targets = use_call_targets;
+        self
+    }
 
-            reference_graphs.par_iter().for_each(|graph| {
-                let matches_list: Arc<Mutex<&mut Vec<BinaryMatch>>> = matches_list.clone();
-                let matches: BinaryMatch = self.compare_graph_sets(sample_graph_ref, graph.borrow());
+    /// Sets whether instructions are compared by their coarse operation class (arithmetic, branch,
+    /// load, store, call, ...) rather than by their bytes.
+    ///
+    /// Allows measuring the structural similarity of binaries built for different architectures.
+    /// The fidelity is limited: unrelated blocks with the same shape hash-match, thresholds should
+    /// be raised accordingly.
+    pub fn with_cross_arch(mut self, cross_arch: bool) -> Self {
+        self.normalization.cross_arch = cross_arch;
+        self
+    }
 
-                matches_list
-                    .lock()
-                    .expect("Unexpected error while aggregating matches")
-                    .push(matches);
-            });
-        }
+    /// Sets the instruction count below which blocks are left out of the graph hashes.
+    ///
+    /// Blocks of one or two instructions collide heavily, so that graphs differing only in their
+    /// short blocks hash-match and are reported as exact matches. Leaving them out changes every
+    /// graph hash and lowers the hit rate of the hash fast path, the blocks are still part of the
+    /// detailed comparison. Disabled (`0`) by default.
+    pub fn with_min_block_instructions(mut self, min_block_instructions: usize) -> Self {
+        self.normalization.min_block_instructions = min_block_instructions;
+        self
+    }
 
-        let compute_elapsed: Duration = compute_start.elapsed();
-        CompareReport::new(&sample_graph_ref.name, matches_list, compute_elapsed)
+    /// Sets whether NOP and padding instructions are left out when hashing and comparing blocks.
+    ///
+    /// Alignment NOPs differ between builds of the same code, with this option blocks and graphs
+    /// differing only in their padding hash-match. Every hash containing such instructions changes,
+    /// hashes are only comparable between disassemblies normalized with the same setting. Disabled
+    /// by default.
+    pub fn with_strip_nops(mut self, strip_nops: bool) -> Self {
+        self.normalization.strip_nops = strip_nops;
+        self
     }
 
-    /// Generate the Control Flow Graph (CFG) for each sample.
+    /// Sets the normalization mode used to hash and compare instructions.
     ///
-    /// The `sample_list` is a list of paths to each sample to dissassemble.
-    #[allow(clippy::assigning_clones)]
-    pub fn generate_graphs(
-        &self,
-        sample_list: &[(String, PathBuf)],
-    ) -> Result<Vec<Disassembly>, Error> {
-        let mut samples_graph: Vec<Disassembly> = Vec::with_capacity(sample_list.len());
+    /// Disassemblies generated with a different mode are re-normalized before being compared, so
+    /// that semantically identical functions hash-match under this mode.
+    pub fn with_normalization(mut self, normalization: NormalizationMode) -> Self {
+        self.normalization = normalization;
+        self
+    }
 
-        // Generate the graph for each sample in separate threads.
-        {
-            let samples_graph: Arc<Mutex<&mut Vec<Disassembly>>> =
-                Arc::new(Mutex::new(&mut samples_graph));
+    /// The normalization mode used to hash and compare instructions.
+    #[inline]
+    pub fn normalization(&self) -> NormalizationMode {
+        self.normalization
+    }
 
-            let mut progress_style: Option<ProgressStyle> = None;
-            if self.display_progress {
-                progress_style = Some(
-                    ProgressStyle::with_template(
-                        "{spinner:.green} [{elapsed_precise}] {msg:.yellow}",
-                    )
-                    .expect("Unable to set spinner template"),
-                );
-            }
+    /// Sets whether a function is skipped when matched against itself.
+    ///
+    /// Comparing a disassembly to itself surfaces internal code clones, skipping the function
+    /// itself avoids trivially matching every function with a similarity of 1.0.
+    pub fn with_skip_self(mut self, skip_self: bool) -> Self {
+        self.skip_self = skip_self;
+        self
+    }
 
-            sample_list.par_iter().try_for_each(|(version, sample_path)| -> Result<(), Error> {
-                let samples_graph: Arc<Mutex<&mut Vec<Disassembly>>> =
-                    samples_graph.clone();
+    /// Sets the strategy used to pool the best block similarities into a graph similarity.
+    pub fn with_pooling(mut self, pooling: Pooling) -> Self {
+        self.pooling = pooling;
+        self
+    }
 
-                let progress_style: Option<ProgressStyle> = progress_style.clone();
-                let mut _spinner: Option<ProgressBar> = None;
-
-                if let Some(multiprogress) = self.multiprogress.clone().deref() {
-                    if let Some(progress_style) = progress_style {
-                        let new_spinner: ProgressBar =
-                            multiprogress.add(ProgressBar::new_spinner());
-                        new_spinner.set_style(progress_style);
-                        new_spinner.enable_steady_tick(Duration::from_millis(100));
-                        new_spinner.set_message(format!("Disassembling {version} ..."));
-                        _spinner = Some(new_spinner);
-                    }
+    /// Weight instruction matches by their rarity across the `reference_graphs` corpus.
+    ///
+    /// Computes the inverse document frequency of each instruction key once, each function of the
+    /// references being a document. Common instructions (`mov`, `push`, ...) then weigh less than
+    /// rare distinctive ones in the similarity, lowering the score of unrelated functions sharing
+    /// only boilerplate. The scores depend on the fitted corpus and are only comparable between
+    /// runs fitted on the same references. Must be called again if the normalization changes.
+    pub fn fit_idf<T: Borrow<Disassembly>>(&mut self, reference_graphs: &[T]) {
+        let mut frequencies: HashMap<String, usize> = HashMap::new();
+        let mut documents: usize = 0;
+
+        for disassembly in reference_graphs {
+            let disassembly: Cow<Disassembly> = self.normalized(disassembly.borrow());
+            for graph in &disassembly.graphs {
+                documents += 1;
+                let keys: HashSet<&String> = graph.blocks.iter().flat_map(|block| &block.keys).collect();
+                for key in keys {
+                    *frequencies.entry(key.clone()).or_default() += 1;
                 }
+            }
+        }
 
-                let mut disassembly: Disassembly = Disassembly::new(sample_path.as_path())?;
-                disassembly.name = version.clone();
+        let idf = |frequency: usize| ((documents as f32 + 1.0) / (frequency as f32 + 1.0)).ln() + 1.0;
+        self.idf = Some(Arc::new(InstructionWeights {
+            weights: frequencies.into_iter().map(|(key, frequency)| (key, idf(frequency))).collect(),
+            unseen: idf(0),
+        }));
+    }
 
-                samples_graph
-                    .lock()
-                    .expect("Unexpected error while aggregating disassemblies")
-                    .push(disassembly);
+    /// Stop weighting instruction matches by their rarity.
+    pub fn clear_idf(&mut self) {
+        self.idf = None;
+    }
 
-                Ok(())
-            })?;
+    /// Sets the block count `k` at which a method match reaches ~63% of its similarity as confidence.
+    ///
+    /// See `MethodMatch::confidence`, larger values penalize small methods more.
+    ///
+    /// Fails with `Error::InvalidOption` if the scale is not positive.
+    pub fn with_confidence_scale(mut self, confidence_scale: f32) -> Result<Self, Error> {
+        if confidence_scale.is_nan() || confidence_scale <= 0.0 {
+            return Err(Error::InvalidOption {
+                option: "confidence_scale".to_string(),
+                reason: format!("{confidence_scale} is not positive"),
+            });
         }
-
-        Ok(samples_graph)
+        self.confidence_scale = confidence_scale;
+        Ok(self)
     }
 
-    // Compare two sets of instruction and return their normalized similarity.
-    fn compare_instructions(lhs_ins: &InstructionStreamer, rhs_ins: &InstructionStreamer) -> f32 {
-        // NOTE: We care about duplicates so we can't just hashset the problem away.
-        let (x, y) = if lhs_ins.len() > rhs_ins.len() {
-            (lhs_ins, rhs_ins)
-        } else {
-            (rhs_ins, lhs_ins)
-        };
-        let mut other: Vec<&String> = y.iter().map(|i| &i.bytes).collect();
-        let mut intersection = 0;
-        let mut union = 0;
-        for instr in x.iter() {
-            union += 1;
-            if let Some(i) = other.iter().position(|x| x == &&instr.bytes) {
-                intersection += 1;
-                other.swap_remove(i);
-            }
-        }
-        union += other.len();
+    /// Sets whether method matches record the alignment of their blocks.
+    ///
+    /// Each block of the sample method is paired with its most similar reference block, see
+    /// `MethodMatch::block_alignment`. Disabled by default as it greatly increases report sizes.
+    pub fn with_block_alignment(mut self, block_alignment: bool) -> Self {
+        self.block_alignment = block_alignment;
+        self
+    }
 
-        if union == 0 {
-            return 1.0;
-        }
+    /// Sets whether instructions differing by a single byte are considered equal.
+    ///
+    /// Catches semantically identical instructions differing by a prefix or a register encoding.
+    /// Every unmatched instruction is then compared against all the others of the block pair, which
+    /// can slow comparisons down by an order of magnitude on dissimilar functions. Resolved call
+    /// targets (see `with_call_targets`) must still be equal, and `cross_arch` disables the option.
+    pub fn with_fuzzy_bytes(mut self, fuzzy_bytes: bool) -> Self {
+        self.fuzzy_bytes = fuzzy_bytes;
+        self
+    }
 
-        intersection as f32 / union as f32
+    /// Cancel the comparisons running on this grapher, from any thread.
+    ///
+    /// The running comparisons bail out early and return partial results, only holding the
+    /// references that were entirely compared. The flag is shared between the clones of the
+    /// grapher and stays set, cancelling any later comparison, until `reset_cancel` is called.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
     }
 
-    // Compare two basic blocks and return their normalized similarity.
-    fn compare_blocks(
-        l_blocks: &[BasicBlock],
-        l_index: usize,
-        r_blocks: &[BasicBlock],
-        r_index: usize,
-    ) -> f32 {
-        let local_sim: f32 = if l_blocks[l_index].hash == r_blocks[r_index].hash {
-            1.0
-        } else {
-            // Compare compare local instruction set.
-            Grapher::compare_instructions(
-                &InstructionStreamer::new(l_blocks, &[l_index]),
-                &InstructionStreamer::new(r_blocks, &[r_index]),
-            )
-        };
+    /// Whether the grapher was cancelled, in which case comparison results may be partial.
+    #[inline]
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
 
-        // Get previous instruction sets.
-        let l_prev_ins = InstructionStreamer::new(l_blocks, &l_blocks[l_index].in_refs);
-        let r_prev_ins = InstructionStreamer::new(r_blocks, &r_blocks[r_index].in_refs);
+    /// Clear the cancellation flag so that the grapher can be used again.
+    pub fn reset_cancel(&self) {
+        self.cancelled.store(false, Ordering::Relaxed);
+    }
 
-        // Get next instruction sets.
-        let l_next_ins = InstructionStreamer::new(l_blocks, &l_blocks[l_index].out_refs);
-        let r_next_ins = InstructionStreamer::new(r_blocks, &r_blocks[r_index].out_refs);
+    /// The settings of the grapher, as recorded in the reports it produces.
+    pub fn config(&self) -> CompareConfig {
+        CompareConfig {
+            threshold: self.threshold,
+            normalization: self.normalization,
+            pooling: self.pooling,
+            skip_self: self.skip_self,
+            idf_weighted: self.idf.is_some(),
+            confidence_scale: self.confidence_scale,
+            block_alignment: self.block_alignment,
+            fuzzy_bytes: self.fuzzy_bytes,
+            fast_block_overlap: self.fast_block_overlap,
+            alignment: self.alignment,
+            size_ratio_gate: self.size_ratio_gate,
+            graph_norm: self.graph_norm,
+            block_weighting: self.block_weighting,
+            strict: self.strict,
+            epsilon: self.epsilon,
+            report_floor: self.report_floor,
+            prefix_blocks: self.prefix_blocks.map(NonZeroUsize::get),
+            bloom_prefilter: self.bloom_prefilter,
+            max_reference_graphs: self.max_reference_graphs,
+            chunk_size: self.chunk_size.map(NonZeroUsize::get),
+        }
+    }
 
-        // Compare previous and next instruction sets.
-        let prev_sim: f32 = Grapher::compare_instructions(&l_prev_ins, &r_prev_ins);
-        let next_sim: f32 = Grapher::compare_instructions(&l_next_ins, &r_next_ins);
+    /// Sets whether graphs are compared by the overlap of their block hashes only.
+    ///
+    /// The fraction of identical blocks, over the block count of the smaller graph, replaces the
+    /// quadratic block by block comparison. This is linear in the number of blocks and much faster,
+    /// but blocks differing by a single instruction no longer contribute to the similarity. The
+    /// precise comparison remains the default.
+    ///
+    /// Scores drift down from the precise ones: comparing coreutils' `mv` to `cp` and `touch`, the
+    /// pairs matched by either mode scored 0.30 lower on average (median 0.24, at most 0.96) and never
+    /// higher, and 108 of the 221 matches remained at a 0.6 threshold. Lower thresholds accordingly.
+    pub fn with_fast_block_overlap(mut self, fast_block_overlap: bool) -> Self {
+        self.fast_block_overlap = fast_block_overlap;
+        self
+    }
 
-        // Compute the overall similarity.
-        ((local_sim * 2.0) + prev_sim + next_sim) / 4.0
+    /// Sets how the progress of disassemblies and comparisons is reported.
+    pub fn with_progress_mode(mut self, progress_mode: ProgressMode) -> Self {
+        self.progress_mode = progress_mode;
+        self.multiprogress = match progress_mode {
+            ProgressMode::Bar => Arc::new(Some(MultiProgress::new())),
+            ProgressMode::None | ProgressMode::Log => Arc::new(None),
+        };
+        self
     }
 
-    // Compare two Control Flow Graphs (CFG) and return their normalized similarity.
-    fn compare_graphs(source_graph: &ControlFlowGraph, target_graph: &ControlFlowGraph) -> f32 {
-        // Graph as most similar if their hashes match.
-        if source_graph.hash == target_graph.hash {
-            return 1.0;
-        }
+    /// Sets the templates and characters of the progress bars and spinners.
+    pub fn with_progress_theme(mut self, progress_theme: ProgressTheme) -> Self {
+        self.progress_theme = progress_theme;
+        self
+    }
 
-        let l_blocks: &[BasicBlock] = &source_graph.blocks;
-        let r_blocks: &[BasicBlock] = &target_graph.blocks;
+    /// Sets how the blocks of two graphs are paired before their similarities are pooled.
+    ///
+    /// `Alignment::Hungarian` pairs blocks one to one, so that a single block can no longer stand
+    /// in for several blocks of the other graph. It adds an `O(n^3)` assignment, in the block count
+    /// of the larger graph, to every graph comparison that is not an exact hash match. The block
+    /// similarity matrix remains the dominant cost for small graphs.
+    pub fn with_alignment(mut self, alignment: Alignment) -> Self {
+        self.alignment = alignment;
+        self
+    }
 
-        let mut top_sims: Vec<f32> = Vec::with_capacity(l_blocks.len());
-        for l_index in 0..l_blocks.len() {
-            let mut current_sim: f32 = 0.0;
-            for r_index in 0..r_blocks.len() {
-                let similarity: f32 = Grapher::compare_blocks(l_blocks, l_index, r_blocks, r_index);
-                if similarity > current_sim {
-                    current_sim = similarity
-                }
-            }
-            top_sims.push(current_sim);
+    /// Sets the block count ratio below which graph pairs are not compared.
+    ///
+    /// A pair is only compared if the block count of the smaller graph is at least `ratio` times the
+    /// block count of the larger one, ie: if their ratio lies within `[ratio, 1 / ratio]`. This is a
+    /// recall / speed tradeoff: pruned pairs could only reach a low similarity under mean pooling,
+    /// but a small function embedded in a larger one is missed. Disabled (`None`) by default.
+    ///
+    /// Fails with `Error::InvalidOption` if the ratio is not within `(0, 1]`.
+    pub fn with_size_ratio_gate(mut self, ratio: Option<f32>) -> Result<Self, Error> {
+        if let Some(ratio) = ratio.filter(|ratio| !(*ratio > 0.0 && *ratio <= 1.0)) {
+            return Err(Error::InvalidOption {
+                option: "size_ratio_gate".to_string(),
+                reason: format!("{ratio} is not within (0, 1]"),
+            });
         }
-        top_sims.sort_unstable_by(|x, y| x.total_cmp(y).reverse());
+        self.size_ratio_gate = ratio;
+        Ok(self)
+    }
 
-        let sample_size: usize = std::cmp::min(l_blocks.len(), r_blocks.len());
-        top_sims[..sample_size].iter().sum::<f32>() / sample_size as f32
+    /// Sets the JSONL file `compare` checkpoints the completed references to.
+    ///
+    /// Each reference's match is appended to the file as soon as it completes. When `compare` is
+    /// run again with the same sample, the references already present in the checkpoint are not
+    /// compared again, their checkpointed matches being reported instead. References are identified
+    /// by name, the checkpoint should be deleted if the inputs or settings change. A checkpoint that
+    /// can not be opened is logged and ignored.
+    pub fn with_checkpoint_path(mut self, checkpoint_path: Option<PathBuf>) -> Self {
+        self.checkpoint_path = checkpoint_path;
+        self
     }
 
-    // Compare a Control Flow Graph (CFG) against a set of Control Flow Graphs and return the best match.
-    fn compare_against_graphs(
-        &self,
-        reference_graph: &ControlFlowGraph,
-        sample_graphs: &Disassembly,
-    ) -> Option<MethodMatch> {
-        let mut current_top: Option<MethodMatch> = None;
+    /// Sets the block count graph similarities are normalized by.
+    ///
+    /// The pooled block similarities are scaled by `min(l, r) / norm(l, r)`, so that `GraphNorm::Max`
+    /// and `GraphNorm::Mean` penalize graphs of different sizes. With the default mean pooling, this
+    /// amounts to dividing the summed best block similarities by `norm(l, r)` instead of `min(l, r)`.
+    pub fn with_graph_norm(mut self, graph_norm: GraphNorm) -> Self {
+        self.graph_norm = graph_norm;
+        self
+    }
 
-        for sample_graph in &sample_graphs.graphs {
-            let similarity: f32 = Grapher::compare_graphs(reference_graph, sample_graph);
-            // Check if the match if significant.
-            if similarity < self.threshold {
-                continue;
-            }
+    /// Sets how the best block similarities are weighted when pooled into a graph similarity.
+    ///
+    /// Each block of the first graph is weighted, along with its best similarity, by
+    /// `BlockWeighting::weight`. The pooling still selects the best similarities, which are then
+    /// averaged by weight. `Pooling::Max` only considers a single block and is not affected.
+    pub fn with_block_weighting(mut self, block_weighting: BlockWeighting) -> Self {
+        self.block_weighting = block_weighting;
+        self
+    }
 
-            // If so, handle it.
-            let current_match = MethodMatch::new(sample_graph, reference_graph, similarity);
-            if similarity >= 1.0 {
-                current_top = Some(current_match);
-                break;
-            }
+    /// Sets whether every graph similarity is checked to be finite and within `[0, 1]`.
+    ///
+    /// A debugging aid, failing loudly rather than propagating a NaN or infinite similarity into the
+    /// report: the comparisons then return an `Error::InvalidSimilarity` identifying the first faulty
+    /// pair of functions. Disabled by default.
+    pub fn with_strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
 
-            match current_top {
-                Some(ref top) => {
-                    if similarity > top.similarity {
-                        current_top = Some(current_match);
-                    }
-                }
-                None => {
-                    current_top = Some(current_match);
-                }
-            }
+    /// Sets the tolerance of the similarity comparisons, `1e-6` by default.
+    ///
+    /// Similarities accumulate `f32` rounding errors, a similarity within `epsilon` of `1.0` is
+    /// considered an exact match, ending the search for the best match, and a similarity must exceed
+    /// the current best by more than `epsilon` to replace it, the first match winning ties.
+    ///
+    /// Fails with `Error::InvalidOption` if the tolerance is not within `[0, 1)`.
+    pub fn with_epsilon(mut self, epsilon: f32) -> Result<Self, Error> {
+        if !(0.0..1.0).contains(&epsilon) {
+            return Err(Error::InvalidOption {
+                option: "epsilon".to_string(),
+                reason: format!("{epsilon} is not within [0, 1)"),
+            });
         }
+        self.epsilon = epsilon;
+        Ok(self)
+    }
 
-        current_top
+    /// Sets the similarity below which method matches are left out of the reports, `0.0` by default.
+    ///
+    /// Unlike the threshold, the floor is applied once each reference was compared: the similarity
+    /// and containment of the binary matches are still computed from every match reaching the
+    /// threshold, only the listed method matches are affected. Running with a threshold of 0 and a
+    /// floor keeps the aggregates of an exhaustive comparison without serializing its noise.
+    ///
+    /// Fails with `Error::InvalidOption` if the floor is not within `[0, 1]`.
+    pub fn with_report_floor(mut self, report_floor: f32) -> Result<Self, Error> {
+        if !(0.0..=1.0).contains(&report_floor) {
+            return Err(Error::InvalidOption {
+                option: "report_floor".to_string(),
+                reason: format!("{report_floor} is not within [0, 1]"),
+            });
+        }
+        self.report_floor = report_floor;
+        Ok(self)
     }
 
-    // Compare two control flow graphs.
-    fn compare_graph_sets(
-        &self,
-        sample_graphs: &Disassembly,
-        reference_graphs: &Disassembly,
-    ) -> BinaryMatch {
-        let mut progress_bar: Arc<Option<ProgressBar>> = Arc::new(None);
+    /// Sets the number of leading blocks, by offset, to which graph comparisons are limited.
+    ///
+    /// An ultra-fast prefilter: only the entry region of each function is compared, the edges
+    /// leaving it being ignored, and similarities are normalized by the prefix sizes. Functions
+    /// sharing their entry but diverging afterwards score as if identical, while functions whose
+    /// entry was modified (such as by an inserted stack check) are missed, so the prefix match
+    /// only shortlists candidates. A two-stage pipeline compares the sample with a low threshold
+    /// and prefix, then compares the `CompareReport::extract_matched` survivors without prefix.
+    /// Hash matches and the `fast_block_overlap` comparison still consider every block.
+    pub fn with_prefix_blocks(mut self, prefix_blocks: Option<NonZeroUsize>) -> Self {
+        self.prefix_blocks = prefix_blocks;
+        self
+    }
 
-        if let Some(multiprogress) = self.multiprogress.clone().deref() {
-            let new_progress_bar: ProgressBar = multiprogress.add(
-                ProgressBar::new(reference_graphs.graphs.len() as u64)
-            );
-            new_progress_bar.set_style(ProgressStyle::with_template(
-                    "[{elapsed_precise} - {eta}] {msg:.yellow} [{wide_bar:.yellow/red}] {pos}/{len} ({percent} %)"
-                ).expect("Unable to set progress bar template").progress_chars("#>-"));
-            progress_bar = Arc::new(Some(new_progress_bar));
+    /// Sets the estimated key overlap below which graph pairs are rejected without being compared.
+    ///
+    /// Each graph holds a Bloom filter of its instruction keys, from which the fraction of the keys
+    /// of the smaller graph found in the other is estimated in constant time. Pairs below the bound
+    /// are given a similarity of 0.0, skipping the quadratic block comparison. Bloom filters never
+    /// miss a key they hold, their false positives only over-estimate the overlap, letting through
+    /// pairs that are then compared in full: the prefilter trades speed for recall only through the
+    /// bound itself. Instructions matched by `fuzzy_bytes` do not share keys, lower the bound when
+    /// both are enabled. Disabled (`None`) by default.
+    ///
+    /// Fails with `Error::InvalidOption` if the bound is not within `[0, 1]`.
+    pub fn with_bloom_prefilter(mut self, bound: Option<f32>) -> Result<Self, Error> {
+        if let Some(bound) = bound.filter(|bound| !(0.0..=1.0).contains(bound)) {
+            return Err(Error::InvalidOption {
+                option: "bloom_prefilter".to_string(),
+                reason: format!("{bound} is not within [0, 1]"),
+            });
         }
+        self.bloom_prefilter = bound;
+        Ok(self)
+    }
 
-        let matches: Vec<_> = reference_graphs
-            .graphs
-            .par_iter()
-            .filter_map(|reference_graph| {
-                let progress: Arc<Option<ProgressBar>> = progress_bar.clone();
-                if let Some(progress_bar) = progress.deref() {
-                    progress_bar.set_message(format!("Matching {}", reference_graphs.name));
-                }
+    /// Sets the number of leading graphs, by offset, of each reference that are compared.
+    ///
+    /// A smoke testing and debugging convenience, the rest of the reference is ignored without
+    /// copying it, unlike `Disassembly::to_subset` which samples it randomly. The function counts
+    /// and containment of the reports only account for the compared graphs.
+    pub fn with_max_reference_graphs(mut self, max_reference_graphs: Option<usize>) -> Self {
+        self.max_reference_graphs = max_reference_graphs;
+        self
+    }
 
-                let current_match = self.compare_against_graphs(reference_graph, sample_graphs);
+    /// Sets the number of reference blocks whose best similarities are computed at once.
+    ///
+    /// The reference blocks are compared against the sample in windows, the best similarities of
+    /// each window being merged into a running list truncated to what the pooling reads. What is
+    /// bounded is that list, to `chunk_size` plus the retained count: 1 with `max`, `K` with `top_k`
+    /// and the block count of the smaller graph with `mean`. The `mean` pooling of a reference no
+    /// larger than the sample retains every block, the chunked path then saves nothing. Similarities
+    /// are identical to the unchunked path. The `hungarian` alignment needs the full block
+    /// similarity matrix and ignores the chunk size.
+    pub fn with_chunk_size(mut self, chunk_size: Option<NonZeroUsize>) -> Self {
+        self.chunk_size = chunk_size;
+        self
+    }
 
-                if let Some(progress_bar) = progress.deref() {
-                    progress_bar.inc(1);
-                    if progress_bar.position() >= progress_bar.length().expect("Progress bar's length not set") {
-                        progress_bar.finish_and_clear();
-                    }
+    // Returns the disassembly normalized with the grapher's mode, only copying it if needed.
+    fn normalized<'a>(&self, disassembly: &'a Disassembly) -> Cow<'a, Disassembly> {
+        if disassembly.normalization == self.normalization {
+            Cow::Borrowed(disassembly)
+        } else {
+            Cow::Owned(disassembly.normalize(self.normalization))
+        }
+    }
+
+    /// Compare a malware sample to a clean set of libraries and produce a matching pairs reports.
+    ///
+    /// The `sample_graph` is the Control Flow Graph (CFG) of the malware sample to compare and
+    /// `reference_graphs` is the list of reference Control Flow Graphs (CFG) to compare to.
+    ///
+    /// Graph similarities are memoized by their (reference, sample) graph hash pair for the duration
+    /// of the call, duplicate functions being compared once. Graphs with identical hashes are already
+    /// considered identical, the cache is however disabled when `min_block_instructions` is set, as
+    /// graphs that differ in their short blocks then share their hash.
+    ///
+    /// Only fails in strict mode, with the first invalid similarity found, see `with_strict`.
+    pub fn compare<T: Sync + Borrow<Disassembly>>(
+        &self,
+        sample_graph: T,
+        reference_graphs: Vec<T>,
+    ) -> Result<CompareReport, Error> {
+        let grapher: Grapher = Grapher {
+            similarity_cache: (self.normalization.min_block_instructions == 0)
+                .then(|| Arc::new(DashMap::new())),
+            ..self.checked()
+        };
+        let report: CompareReport = grapher.compare_cached(sample_graph, reference_graphs);
+        grapher.strict_result(report)
+    }
+
+    // A copy of the grapher recording the invalid similarities of a single call, see `strict_result`.
+    fn checked(&self) -> Grapher {
+        Grapher {
+            invalid_similarity: Arc::new(Mutex::new(None)),
+            ..self.clone()
+        }
+    }
+
+    // Returns the result of a call on a `checked` grapher, failing with the first invalid similarity.
+    fn strict_result<R>(&self, result: R) -> Result<R, Error> {
+        let invalid_similarity: Option<Error> = self
+            .invalid_similarity
+            .lock()
+            .expect("Unexpected error while checking similarities")
+            .take();
+        match invalid_similarity {
+            Some(error) => Err(error),
+            None => Ok(result),
+        }
+    }
+
+    // Compare a malware sample to a clean set of libraries, with the grapher's similarity cache.
+    fn compare_cached<T: Sync + Borrow<Disassembly>>(
+        &self,
+        sample_graph: T,
+        reference_graphs: Vec<T>,
+    ) -> CompareReport {
+        let _span = tracing::debug_span!(
+            "compare",
+            sample = %sample_graph.borrow().name,
+            references = reference_graphs.len(),
+        )
+        .entered();
+        let compute_start: Instant = Instant::now();
+        let sample_graph: Cow<Disassembly> = self.normalized(sample_graph.borrow());
+        let sample_graph_ref: &Disassembly = &sample_graph;
+        let reference_graphs: Vec<Cow<Disassembly>> = reference_graphs
+            .iter()
+            .map(|graph| self.normalized(graph.borrow()))
+            .collect();
+        let mut matches_list: Vec<BinaryMatch> = Vec::with_capacity(reference_graphs.len());
+
+        // Resume from the references already checkpointed.
+        let checkpoint: Option<Checkpoint> = self.checkpoint_path.as_deref().and_then(|path| {
+            Checkpoint::open(path, &sample_graph_ref.name)
+                .inspect_err(|error| tracing::warn!(%error, "checkpoint ignored"))
+                .ok()
+        });
+        if let Some(checkpoint) = &checkpoint {
+            matches_list.extend(
+                reference_graphs
+                    .iter()
+                    .filter_map(|graph| checkpoint.completed(&graph.name))
+                    .cloned(),
+            );
+        }
+        let resumed: usize = matches_list.len();
+
+        // Track the overall number of references compared.
+        let mut overall_progress: Option<ProgressBar> = None;
+        if let Some(multiprogress) = self.multiprogress.clone().deref() {
+            let new_progress_bar: ProgressBar = multiprogress.add(
+                ProgressBar::new(reference_graphs.len() as u64)
+            );
+            new_progress_bar.set_style(self.bar_style(&self.progress_theme.overall_template));
+            new_progress_bar.set_message(format!("Comparing {}", sample_graph_ref.name));
+            new_progress_bar.set_position(resumed as u64);
+            overall_progress = Some(new_progress_bar);
+        }
+
+        { // Compare each sample graph.
+            let matches_list: Arc<Mutex<&mut Vec<BinaryMatch>>> =
+                Arc::new(Mutex::new(&mut matches_list));
+            let compared: AtomicUsize = AtomicUsize::new(resumed);
+
+            reference_graphs.par_iter().for_each(|graph| {
+                if self.is_cancelled() {
+                    return;
+                }
+                if checkpoint.as_ref().is_some_and(|checkpoint| checkpoint.completed(&graph.name).is_some()) {
+                    return;
+                }
+
+                let matches_list: Arc<Mutex<&mut Vec<BinaryMatch>>> = matches_list.clone();
+                let matches: BinaryMatch = self.compare_graph_sets(sample_graph_ref, graph);
+
+                // Drop the references interrupted midway, their matches are incomplete.
+                if self.is_cancelled() {
+                    return;
+                }
+                if let Some(checkpoint) = &checkpoint {
+                    checkpoint.record(&matches);
+                }
+
+                matches_list
+                    .lock()
+                    .expect("Unexpected error while aggregating matches")
+                    .push(matches);
+
+                if let Some(progress_bar) = &overall_progress {
+                    progress_bar.inc(1);
+                }
+                self.log_progress(
+                    &format!("references compared to {}", sample_graph_ref.name),
+                    compared.fetch_add(1, Ordering::Relaxed) + 1,
+                    reference_graphs.len(),
+                );
+            });
+        }
+
+        if let Some(progress_bar) = overall_progress {
+            progress_bar.finish_and_clear();
+        }
+
+        let compute_elapsed: Duration = compute_start.elapsed();
+        tracing::debug!(
+            references = matches_list.len(),
+            cancelled = self.is_cancelled(),
+            elapsed = ?compute_elapsed,
+            "sample compared",
+        );
+        CompareReport::new(sample_graph_ref, matches_list, compute_elapsed, self.config())
+    }
+
+    /// Compare a malware sample to a clean set of libraries, streaming the result of each reference.
+    ///
+    /// The `callback` is invoked on the calling thread with the `BinaryMatch` of each reference as
+    /// soon as it completes, in completion order. References are still compared in parallel, and
+    /// stop being compared once the grapher is cancelled.
+    ///
+    /// In strict mode, the first invalid similarity found is returned once every reference was
+    /// compared, the matches already streamed are not retracted.
+    pub fn compare_streaming<T, F>(&self, sample_graph: T, reference_graphs: Vec<T>, callback: F) -> Result<(), Error>
+    where
+        T: Sync + Borrow<Disassembly>,
+        F: FnMut(BinaryMatch),
+    {
+        let grapher: Grapher = self.checked();
+        grapher.compare_streaming_checked(sample_graph, reference_graphs, callback);
+        grapher.strict_result(())
+    }
+
+    // Compare a malware sample to a clean set of libraries, streaming the result of each reference.
+    fn compare_streaming_checked<T, F>(&self, sample_graph: T, reference_graphs: Vec<T>, mut callback: F)
+    where
+        T: Sync + Borrow<Disassembly>,
+        F: FnMut(BinaryMatch),
+    {
+        let sample_graph: Cow<Disassembly> = self.normalized(sample_graph.borrow());
+        let sample_graph_ref: &Disassembly = &sample_graph;
+        let reference_graphs: &Vec<T> = &reference_graphs;
+        let (sender, receiver) = mpsc::channel::<BinaryMatch>();
+
+        thread::scope(|scope| {
+            scope.spawn(move || {
+                reference_graphs.par_iter().for_each_with(sender, |sender, graph| {
+                    if self.is_cancelled() {
+                        return;
+                    }
+
+                    let graph: Cow<Disassembly> = self.normalized(graph.borrow());
+                    let matches: BinaryMatch = self.compare_graph_sets(sample_graph_ref, &graph);
+
+                    // Drop the references interrupted midway, their matches are incomplete.
+                    if !self.is_cancelled() {
+                        let _ = sender.send(matches);
+                    }
+                });
+            });
+
+            for matches in receiver {
+                callback(matches);
+            }
+        });
+    }
+
+    /// Compare a malware sample to a set of references disassembled lazily, `batch_size` at a time.
+    ///
+    /// Each batch of references is disassembled, compared and dropped before the next is loaded,
+    /// bounding the memory held to `batch_size` references at the cost of parallelism: a batch size
+    /// of 1 disassembles and compares the references one at a time. References are named after their
+    /// file names.
+    pub fn compare_lazy<T: Sync + Borrow<Disassembly>>(
+        &self,
+        sample_graph: T,
+        reference_paths: &[PathBuf],
+        batch_size: usize,
+    ) -> Result<CompareReport, Error> {
+        // Normalize the sample once rather than once per batch.
+        let sample_graph: Cow<Disassembly> = self.normalized(sample_graph.borrow());
+        let mut report: CompareReport = self.compare(sample_graph.as_ref(), Vec::new())?;
+
+        for batch in reference_paths.chunks(batch_size.max(1)) {
+            let reference_list: Vec<(String, PathBuf)> = batch
+                .iter()
+                .map(|path| {
+                    let filename: String = path
+                        .file_name()
+                        .unwrap_or(path.as_os_str())
+                        .to_string_lossy()
+                        .to_string();
+                    (filename, path.clone())
+                })
+                .collect();
+            let references: Vec<Disassembly> = self.generate_graphs(&reference_list)?;
+            tracing::debug!(references = references.len(), "comparing reference batch");
+            report.append(self.compare(sample_graph.as_ref(), references.iter().collect())?)?;
+        }
+
+        Ok(report)
+    }
+
+    /// Compare several malware samples to the same clean set of libraries.
+    ///
+    /// Samples are compared in parallel, one `CompareReport` is produced for each of the
+    /// `sample_graphs` in the same order. In strict mode, fails with an invalid similarity of any sample.
+    pub fn compare_many<T: Sync + Borrow<Disassembly>>(
+        &self,
+        sample_graphs: Vec<T>,
+        reference_graphs: Vec<T>,
+    ) -> Result<Vec<CompareReport>, Error> {
+        // Normalize the shared references once rather than once per sample.
+        let reference_graphs: Vec<Cow<Disassembly>> = reference_graphs
+            .iter()
+            .map(|graph| self.normalized(graph.borrow()))
+            .collect();
+
+        sample_graphs
+            .par_iter()
+            .map(|sample_graph| {
+                let references: Vec<&Disassembly> = reference_graphs.iter().map(|graph| graph.as_ref()).collect();
+                self.compare(sample_graph.borrow(), references)
+            })
+            .collect()
+    }
+
+    /// Compare a malware sample to the references of a prebuilt signature database.
+    ///
+    /// The references are re-normalized with the grapher's mode if the database was built with
+    /// another one. Only fails in strict mode, see `compare`.
+    pub fn compare_to_db(&self, sample_graph: &Disassembly, db: &SignatureDb) -> Result<CompareReport, Error> {
+        self.compare(sample_graph, db.disassemblies().iter().collect())
+    }
+
+    /// Compare a malware sample to a clean set of libraries and append the results to an existing report.
+    ///
+    /// The `report` must belong to the same sample as `sample_graph`, its compute time is increased
+    /// by the time spent on this comparison.
+    pub fn compare_report_into<T: Sync + Borrow<Disassembly>>(
+        &self,
+        sample_graph: T,
+        reference_graphs: Vec<T>,
+        report: &mut CompareReport,
+    ) -> Result<(), Error> {
+        let sample_name: &String = &sample_graph.borrow().name;
+        if sample_name != report.sample_name() {
+            return Err(Error::SampleNameMismatch {
+                expected: report.sample_name().clone(),
+                found: sample_name.clone(),
+            });
+        }
+
+        report.append(self.compare(sample_graph, reference_graphs)?)
+    }
+
+    /// Disassemble a malware sample and a set of references, then compare them in one operation.
+    ///
+    /// The sample is named after its file name and each of the `reference_paths` after its given
+    /// version, as with `generate_graphs`.
+    pub fn analyze(
+        &self,
+        sample_path: &Path,
+        reference_paths: &[(String, PathBuf)],
+    ) -> Result<CompareReport, Error> {
+        let sample_name: String = sample_path
+            .file_name()
+            .unwrap_or(sample_path.as_os_str())
+            .to_string_lossy()
+            .to_string();
+        let mut sample_list: Vec<(String, PathBuf)> = reference_paths.to_vec();
+        sample_list.push((sample_name, sample_path.to_path_buf()));
+
+        let mut disassemblies: Vec<Disassembly> = self.generate_graphs(&sample_list)?;
+        let Some(sample_index) = disassemblies
+            .iter()
+            .position(|disassembly| disassembly.path == sample_path)
+        else {
+            return Err(Error::SampleNotFound {
+                sample: sample_path.to_string_lossy().to_string(),
+            });
+        };
+        let sample_graph: Disassembly = disassemblies.swap_remove(sample_index);
+
+        self.compare(sample_graph, disassemblies)
+    }
+
+    /// Disassemble several malware samples and a set of references, then compare each sample to the
+    /// references in one operation.
+    ///
+    /// One `CompareReport` is produced for each of the `sample_paths` in the same order, the samples
+    /// being named after their file names, see `analyze` and `compare_many`.
+    pub fn analyze_many(
+        &self,
+        sample_paths: &[PathBuf],
+        reference_paths: &[(String, PathBuf)],
+    ) -> Result<Vec<CompareReport>, Error> {
+        let mut sample_list: Vec<(String, PathBuf)> = reference_paths.to_vec();
+        sample_list.extend(sample_paths.iter().map(|sample_path| {
+            let sample_name: String = sample_path
+                .file_name()
+                .unwrap_or(sample_path.as_os_str())
+                .to_string_lossy()
+                .to_string();
+            (sample_name, sample_path.clone())
+        }));
+
+        let (mut samples, references): (Vec<Disassembly>, Vec<Disassembly>) = self
+            .generate_graphs(&sample_list)?
+            .into_iter()
+            .partition(|disassembly| sample_paths.contains(&disassembly.path));
+        let sample_graphs: Vec<Disassembly> = sample_paths
+            .iter()
+            .map(|sample_path| {
+                match samples.iter().position(|disassembly| &disassembly.path == sample_path) {
+                    Some(sample_index) => Ok(samples.swap_remove(sample_index)),
+                    None => Err(Error::SampleNotFound {
+                        sample: sample_path.to_string_lossy().to_string(),
+                    }),
+                }
+            })
+            .collect::<Result<_, Error>>()?;
+
+        self.compare_many(sample_graphs, references)
+    }
+
+    /// Generate the Control Flow Graph (CFG) for each sample.
+    ///
+    /// The `sample_list` is a list of paths to each sample to dissassemble.
+    pub fn generate_graphs(
+        &self,
+        sample_list: &[(String, PathBuf)],
+    ) -> Result<Vec<Disassembly>, Error> {
+        let mut samples_graph: Vec<Disassembly> = Vec::with_capacity(sample_list.len());
+
+        // Generate the graph for each sample in separate threads.
+        {
+            let samples_graph: Arc<Mutex<&mut Vec<Disassembly>>> =
+                Arc::new(Mutex::new(&mut samples_graph));
+            let progress_style: Option<ProgressStyle> = self.spinner_style();
+            let disassembled: AtomicUsize = AtomicUsize::new(0);
+
+            sample_list.par_iter().try_for_each(|(version, sample_path)| -> Result<(), Error> {
+                let samples_graph: Arc<Mutex<&mut Vec<Disassembly>>> =
+                    samples_graph.clone();
+
+                let disassembly: Disassembly =
+                    self.disassemble_sample(version, sample_path, &progress_style)?;
+                self.log_progress(
+                    "samples disassembled",
+                    disassembled.fetch_add(1, Ordering::Relaxed) + 1,
+                    sample_list.len(),
+                );
+
+                samples_graph
+                    .lock()
+                    .expect("Unexpected error while aggregating disassemblies")
+                    .push(disassembly);
+
+                Ok(())
+            })?;
+        }
+
+        Ok(samples_graph)
+    }
+
+    /// Generate the Control Flow Graph (CFG) for each sample, carrying on past failing samples.
+    ///
+    /// Returns every successful disassembly along with the path and error of each failed sample.
+    pub fn generate_graphs_lenient(
+        &self,
+        sample_list: &[(String, PathBuf)],
+    ) -> LenientGraphs {
+        let mut samples_graph: Vec<Disassembly> = Vec::with_capacity(sample_list.len());
+        let mut failures: Vec<(PathBuf, Error)> = Vec::new();
+
+        // Generate the graph for each sample in separate threads.
+        {
+            let samples_graph: Arc<Mutex<&mut Vec<Disassembly>>> =
+                Arc::new(Mutex::new(&mut samples_graph));
+            let failures: Arc<Mutex<&mut Vec<(PathBuf, Error)>>> =
+                Arc::new(Mutex::new(&mut failures));
+            let progress_style: Option<ProgressStyle> = self.spinner_style();
+            let disassembled: AtomicUsize = AtomicUsize::new(0);
+
+            sample_list.par_iter().for_each(|(version, sample_path)| {
+                let disassembly: Result<Disassembly, Error> =
+                    self.disassemble_sample(version, sample_path, &progress_style);
+                self.log_progress(
+                    "samples disassembled",
+                    disassembled.fetch_add(1, Ordering::Relaxed) + 1,
+                    sample_list.len(),
+                );
+
+                match disassembly {
+                    Ok(disassembly) => samples_graph
+                        .lock()
+                        .expect("Unexpected error while aggregating disassemblies")
+                        .push(disassembly),
+                    Err(error) => failures
+                        .lock()
+                        .expect("Unexpected error while aggregating failures")
+                        .push((sample_path.clone(), error)),
+                }
+            });
+        }
+
+        (samples_graph, failures)
+    }
+
+    /// Compute the similarity of two methods, as used to score method matches.
+    ///
+    /// Both graphs are re-normalized with the grapher's mode first. The threshold is not applied,
+    /// which makes this the minimal reproduction of a single match score. Only fails in strict mode,
+    /// if the similarity is invalid, `lhs` being reported as the reference.
+    pub fn graph_similarity(&self, lhs: &ControlFlowGraph, rhs: &ControlFlowGraph) -> Result<f32, Error> {
+        let mut lhs: ControlFlowGraph = lhs.clone();
+        let mut rhs: ControlFlowGraph = rhs.clone();
+        lhs.normalize(self.normalization);
+        rhs.normalize(self.normalization);
+        let grapher: Grapher = self.checked();
+        let similarity: f32 = grapher.compare_graphs(&lhs, &rhs);
+        grapher.check_similarity(similarity, &lhs, &rhs);
+        grapher.strict_result(similarity)
+    }
+
+    /// Compute the similarity of every pair of blocks of two methods, as pooled into their similarity.
+    ///
+    /// Both graphs are re-normalized with the grapher's mode first. Rows are the blocks of `lhs`
+    /// and columns the blocks of `rhs`, in offset order and limited to `prefix_blocks` if set. The
+    /// hash fast path is not taken, identical methods yield their full matrix.
+    pub fn block_similarity_matrix(&self, lhs: &ControlFlowGraph, rhs: &ControlFlowGraph) -> Vec<Vec<f32>> {
+        let mut lhs: ControlFlowGraph = lhs.clone();
+        let mut rhs: ControlFlowGraph = rhs.clone();
+        lhs.normalize(self.normalization);
+        rhs.normalize(self.normalization);
+        let (l_blocks, r_blocks): (&[BasicBlock], &[BasicBlock]) = self.compared_blocks(&lhs.blocks, &rhs.blocks);
+        self.block_similarities(l_blocks, r_blocks)
+    }
+
+    /// Score specific (sample_offset, reference_offset) function pairs, such as ground truth matches.
+    ///
+    /// Returns the (sample_offset, reference_offset, similarity) of each pair, in order. The threshold
+    /// is not applied. Fails on the first offset without a function in its disassembly, or in strict
+    /// mode on the first invalid similarity.
+    pub fn score_pairs(
+        &self,
+        sample: &Disassembly,
+        reference: &Disassembly,
+        pairs: &[(u64, u64)],
+    ) -> Result<Vec<(u64, u64, f32)>, Error> {
+        let sample: Cow<Disassembly> = self.normalized(sample);
+        let reference: Cow<Disassembly> = self.normalized(reference);
+        let sample_graphs: HashMap<u64, &ControlFlowGraph> =
+            sample.graphs.iter().map(|graph| (graph.offset, graph)).collect();
+        let reference_graphs: HashMap<u64, &ControlFlowGraph> =
+            reference.graphs.iter().map(|graph| (graph.offset, graph)).collect();
+
+        fn lookup<'a>(
+            graphs: &HashMap<u64, &'a ControlFlowGraph>,
+            disassembly: &Disassembly,
+            offset: u64,
+        ) -> Result<&'a ControlFlowGraph, Error> {
+            graphs.get(&offset).copied().ok_or_else(|| Error::FunctionNotFound {
+                sample: disassembly.name.clone(),
+                offset,
+            })
+        }
+
+        pairs
+            .iter()
+            .map(|&(sample_offset, reference_offset)| {
+                let sample_graph: &ControlFlowGraph = lookup(&sample_graphs, &sample, sample_offset)?;
+                let reference_graph: &ControlFlowGraph = lookup(&reference_graphs, &reference, reference_offset)?;
+                let grapher: Grapher = self.checked();
+                let similarity: f32 = grapher.compare_graphs(reference_graph, sample_graph);
+                grapher.check_similarity(similarity, reference_graph, sample_graph);
+                Ok((sample_offset, reference_offset, grapher.strict_result(similarity)?))
+            })
+            .collect()
+    }
+
+    /// Compute the instruction level differences between two methods.
+    ///
+    /// Each block of `source` is aligned with its most similar block of `target`, the instructions
+    /// missing from either side of the pair are reported. Target blocks that no source block was
+    /// aligned with are reported as entirely added. Instructions are compared by their normalized
+    /// keys, which are their hex encoded bytes unless a normalization mode is active.
+    pub fn diff_methods(&self, source: &ControlFlowGraph, target: &ControlFlowGraph) -> MethodDiff {
+        let mut source: ControlFlowGraph = source.clone();
+        let mut target: ControlFlowGraph = target.clone();
+        source.normalize(self.normalization);
+        target.normalize(self.normalization);
+
+        let l_blocks: &[BasicBlock] = &source.blocks;
+        let r_blocks: &[BasicBlock] = &target.blocks;
+        let mut aligned: Vec<bool> = vec![false; r_blocks.len()];
+        let mut blocks: Vec<BlockDiff> = Vec::with_capacity(l_blocks.len());
+
+        for l_index in 0..l_blocks.len() {
+            let mut best: Option<(usize, f32)> = None;
+            for r_index in 0..r_blocks.len() {
+                let similarity: f32 = self.compare_blocks(l_blocks, l_index, r_blocks, r_index);
+                if best.is_none_or(|(_, top)| similarity > top) {
+                    best = Some((r_index, similarity));
+                }
+            }
+
+            let l_block: &BasicBlock = &l_blocks[l_index];
+            blocks.push(match best {
+                Some((r_index, similarity)) => {
+                    aligned[r_index] = true;
+                    let r_block: &BasicBlock = &r_blocks[r_index];
+                    BlockDiff::new(
+                        Some(l_block.offset),
+                        Some(r_block.offset),
+                        similarity,
+                        Grapher::missing_keys(&l_block.keys, &r_block.keys),
+                        Grapher::missing_keys(&r_block.keys, &l_block.keys),
+                    )
+                }
+                None => BlockDiff::new(Some(l_block.offset), None, 0.0, l_block.keys.clone(), Vec::new()),
+            });
+        }
+
+        for (r_block, _) in r_blocks.iter().zip(aligned).filter(|(_, aligned)| !aligned) {
+            blocks.push(BlockDiff::new(None, Some(r_block.offset), 0.0, Vec::new(), r_block.keys.clone()));
+        }
+
+        MethodDiff::new(&source.name, &target.name, blocks)
+    }
+
+    // Returns the keys of `lhs` missing from `rhs`, accounting for duplicates.
+    fn missing_keys(lhs: &[String], rhs: &[String]) -> Vec<String> {
+        let mut other: Vec<&String> = rhs.iter().collect();
+        let mut missing: Vec<String> = Vec::new();
+        for key in lhs {
+            match other.iter().position(|x| *x == key) {
+                Some(i) => {
+                    other.swap_remove(i);
+                }
+                None => missing.push(key.clone()),
+            }
+        }
+        missing
+    }
+
+    // Print a progress line for log aggregation, if progress is logged.
+    fn log_progress(&self, stage: &str, done: usize, total: usize) {
+        if self.progress_mode == ProgressMode::Log {
+            eprintln!("progress: {done}/{total} {stage}");
+        }
+    }
+
+    // Returns the style of the disassembly spinners, if progress is displayed.
+    fn spinner_style(&self) -> Option<ProgressStyle> {
+        if self.progress_mode != ProgressMode::Bar {
+            return None;
+        }
+        let style: ProgressStyle = ProgressStyle::with_template(&self.progress_theme.spinner_template)
+            .expect("Unable to set spinner template");
+        Some(match &self.progress_theme.tick_chars {
+            Some(tick_chars) => style.tick_chars(tick_chars),
+            None => style,
+        })
+    }
+
+    // Returns the style of a progress bar with the given template.
+    fn bar_style(&self, template: &str) -> ProgressStyle {
+        ProgressStyle::with_template(template)
+            .expect("Unable to set progress bar template")
+            .progress_chars(&self.progress_theme.progress_chars)
+    }
+
+    // Disassemble a single sample, displaying a spinner while in progress.
+    fn disassemble_sample(
+        &self,
+        version: &str,
+        sample_path: &Path,
+        progress_style: &Option<ProgressStyle>,
+    ) -> Result<Disassembly, Error> {
+        let mut _spinner: Option<ProgressBar> = None;
+
+        if let Some(multiprogress) = self.multiprogress.clone().deref() {
+            if let Some(progress_style) = progress_style.clone() {
+                let new_spinner: ProgressBar =
+                    multiprogress.add(ProgressBar::new_spinner());
+                new_spinner.set_style(progress_style);
+                new_spinner.enable_steady_tick(Duration::from_millis(100));
+                new_spinner.set_message(format!("Disassembling {version} ..."));
+                _spinner = Some(new_spinner);
+            }
+        }
+
+        let mut disassembly: Disassembly =
+            Disassembly::with_normalization(sample_path, self.normalization)?;
+        disassembly.name = version.to_string();
+        Ok(disassembly)
+    }
+
+    // Compare two sets of instruction and return their normalized similarity.
+    fn compare_instructions(&self, lhs_ins: &InstructionStreamer, rhs_ins: &InstructionStreamer) -> f32 {
+        // NOTE: We care about duplicates so we can't just hashset the problem away.
+        let (x, y) = if lhs_ins.len() > rhs_ins.len() {
+            (lhs_ins, rhs_ins)
+        } else {
+            (rhs_ins, lhs_ins)
+        };
+        let mut other: Vec<&String> = y.iter().collect();
+        let mut intersection: f32 = 0.0;
+        let mut union: f32 = 0.0;
+        for instr in x.iter() {
+            let weight: f32 = self.instruction_weight(instr);
+            union += weight;
+            let position: Option<usize> = other.iter().position(|x| x == &instr).or_else(|| {
+                // Fall back to near identical instructions, exact matches being preferred. Operation
+                // classes are not bytes, they are never fuzzy matched.
+                (self.fuzzy_bytes && !self.normalization.cross_arch)
+                    .then(|| other.iter().position(|x| Grapher::within_one_byte_edit(x, instr)))
+                    .flatten()
+            });
+            if let Some(i) = position {
+                intersection += weight;
+                other.swap_remove(i);
+            }
+        }
+        union += other.iter().map(|instr| self.instruction_weight(instr)).sum::<f32>();
+
+        if union == 0.0 {
+            return 1.0;
+        }
+
+        intersection / union
+    }
+
+    // Whether two instruction keys are within a Levenshtein distance of one byte.
+    //
+    // Only the hex encoded bytes are edited, the `:symbol` call target suffixes must be equal.
+    fn within_one_byte_edit(lhs: &str, rhs: &str) -> bool {
+        fn split(key: &str) -> (&str, Option<&str>) {
+            match key.split_once(':') {
+                Some((bytes, symbol)) => (bytes, Some(symbol)),
+                None => (key, None),
+            }
+        }
+        let ((lhs, lhs_symbol), (rhs, rhs_symbol)) = (split(lhs), split(rhs));
+        if lhs_symbol != rhs_symbol {
+            return false;
+        }
+
+        let (lhs, rhs): (&[u8], &[u8]) = (lhs.as_bytes(), rhs.as_bytes());
+        let (short, long) = if lhs.len() <= rhs.len() { (lhs, rhs) } else { (rhs, lhs) };
+        if long.len() - short.len() > 2 {
+            return false;
+        }
+
+        let short: Vec<&[u8]> = short.chunks(2).collect();
+        let long: Vec<&[u8]> = long.chunks(2).collect();
+        let prefix: usize = short.iter().zip(&long).take_while(|(x, y)| x == y).count();
+        if short.len() == long.len() {
+            // A single substituted byte.
+            short[prefix..].iter().skip(1).eq(long[prefix..].iter().skip(1))
+        } else {
+            // A single inserted byte, such as a prefix.
+            short[prefix..].iter().eq(long[prefix + 1..].iter())
+        }
+    }
+
+    // Returns the weight of an instruction key, its inverse document frequency if fitted.
+    fn instruction_weight(&self, key: &String) -> f32 {
+        match self.idf.as_deref() {
+            Some(idf) => idf.weights.get(key).copied().unwrap_or(idf.unseen),
+            None => 1.0,
+        }
+    }
+
+    // Compare two basic blocks and return their normalized similarity.
+    fn compare_blocks(
+        &self,
+        l_blocks: &[BasicBlock],
+        l_index: usize,
+        r_blocks: &[BasicBlock],
+        r_index: usize,
+    ) -> f32 {
+        let local_sim: f32 = if l_blocks[l_index].hash == r_blocks[r_index].hash {
+            1.0
+        } else {
+            // Compare compare local instruction set.
+            self.compare_instructions(
+                &InstructionStreamer::new(l_blocks, &[l_index]),
+                &InstructionStreamer::new(r_blocks, &[r_index]),
+            )
+        };
+
+        // Get previous instruction sets.
+        let l_prev_ins = InstructionStreamer::new(l_blocks, &l_blocks[l_index].in_refs);
+        let r_prev_ins = InstructionStreamer::new(r_blocks, &r_blocks[r_index].in_refs);
+
+        // Get next instruction sets.
+        let l_next_ins = InstructionStreamer::new(l_blocks, &l_blocks[l_index].out_refs);
+        let r_next_ins = InstructionStreamer::new(r_blocks, &r_blocks[r_index].out_refs);
+
+        // Compare previous and next instruction sets.
+        let prev_sim: f32 = self.compare_instructions(&l_prev_ins, &r_prev_ins);
+        let next_sim: f32 = self.compare_instructions(&l_next_ins, &r_next_ins);
+
+        // Compute the overall similarity.
+        ((local_sim * 2.0) + prev_sim + next_sim) / 4.0
+    }
+
+    // Returns the similarity of a block with its most similar block of the other graph, `0` if none.
+    fn best_block_similarity(&self, l_blocks: &[BasicBlock], l_index: usize, r_blocks: &[BasicBlock]) -> f32 {
+        let mut current_sim: f32 = 0.0;
+        for r_index in 0..r_blocks.len() {
+            let similarity: f32 = self.compare_blocks(l_blocks, l_index, r_blocks, r_index);
+            if similarity > current_sim {
+                current_sim = similarity
+            }
+        }
+        current_sim
+    }
+
+    // The blocks of two graphs taking part in their comparison, their prefix with `prefix_blocks`.
+    //
+    // Graph blocks are sorted by offset, their prefix is the entry region of the function.
+    fn compared_blocks<'a>(
+        &self,
+        l_blocks: &'a [BasicBlock],
+        r_blocks: &'a [BasicBlock],
+    ) -> (&'a [BasicBlock], &'a [BasicBlock]) {
+        match self.prefix_blocks {
+            Some(prefix) => {
+                let prefix: usize = prefix.get();
+                (&l_blocks[..prefix.min(l_blocks.len())], &r_blocks[..prefix.min(r_blocks.len())])
+            }
+            None => (l_blocks, r_blocks),
+        }
+    }
+
+    // Compare two Control Flow Graphs (CFG) and return their normalized similarity.
+    fn compare_graphs(&self, source_graph: &ControlFlowGraph, target_graph: &ControlFlowGraph) -> f32 {
+        // Graph as most similar if their blocks match.
+        if source_graph.is_identical(target_graph) {
+            return 1.0;
+        }
+
+        if self
+            .bloom_prefilter
+            .is_some_and(|bound| source_graph.key_filter.overlap(&target_graph.key_filter) < bound)
+        {
+            return 0.0;
+        }
+
+        let l_blocks: &[BasicBlock] = &source_graph.blocks;
+        let r_blocks: &[BasicBlock] = &target_graph.blocks;
+
+        if self.fast_block_overlap {
+            let sample_size: usize = std::cmp::min(l_blocks.len(), r_blocks.len());
+            if sample_size == 0 {
+                return 0.0;
+            }
+            let norm_size: f32 = self.graph_norm.size(l_blocks.len(), r_blocks.len());
+            return source_graph.block_overlap(target_graph) as f32 / norm_size;
+        }
+
+        let key: (u64, u64) = (source_graph.hash, target_graph.hash);
+        if let Some(similarity) = self.similarity_cache.as_ref().and_then(|cache| cache.get(&key).map(|x| *x)) {
+            return similarity;
+        }
+
+        let (l_blocks, r_blocks): (&[BasicBlock], &[BasicBlock]) = self.compared_blocks(l_blocks, r_blocks);
+
+        // The best similarity of each block, along with the weight of the block.
+        let weighted = |l_index: usize, similarity: f32| (similarity, self.block_weighting.weight(&l_blocks[l_index]));
+        let mut top_sims: Vec<(f32, f32)> = match (self.alignment, self.chunk_size) {
+            (Alignment::Greedy, Some(chunk_size)) => {
+                // The first graph is the reference, whose blocks are windowed.
+                let chunk_size: usize = chunk_size.get();
+                let retained: usize = self.pooling.retained(l_blocks.len(), r_blocks.len());
+                let mut top_sims: Vec<(f32, f32)> = Vec::with_capacity(retained + chunk_size);
+                for l_start in (0..l_blocks.len()).step_by(chunk_size) {
+                    let l_end: usize = (l_start + chunk_size).min(l_blocks.len());
+                    top_sims.extend((l_start..l_end).map(|l_index| {
+                        weighted(l_index, self.best_block_similarity(l_blocks, l_index, r_blocks))
+                    }));
+                    Pooling::sort(&mut top_sims);
+                    top_sims.truncate(retained);
+                }
+                top_sims
+            }
+            (Alignment::Greedy, None) => {
+                (0..l_blocks.len())
+                    .map(|l_index| weighted(l_index, self.best_block_similarity(l_blocks, l_index, r_blocks)))
+                    .collect()
+            }
+            (Alignment::Hungarian, _) => {
+                let similarities: Vec<Vec<f32>> = self.block_similarities(l_blocks, r_blocks);
+                Grapher::max_weight_assignment(&similarities)
+                    .into_iter()
+                    .map(|(l_index, r_index)| weighted(l_index, similarities[l_index][r_index]))
+                    .collect()
+            }
+        };
+        Pooling::sort(&mut top_sims);
+
+        let sample_size: usize = std::cmp::min(l_blocks.len(), r_blocks.len());
+        let norm_size: f32 = self.graph_norm.size(l_blocks.len(), r_blocks.len());
+        let similarity: f32 = self.pooling.pool(&top_sims, sample_size) * sample_size as f32 / norm_size;
+        if let Some(cache) = &self.similarity_cache {
+            cache.insert(key, similarity);
+        }
+        similarity
+    }
+
+    // Compare a Control Flow Graph (CFG) against a set of Control Flow Graphs and return the best match.
+    //
+    // When `is_self` is set the sample and reference are the same binary, in which case a function
+    // is never matched against itself if `skip_self` is enabled.
+    fn compare_against_graphs(
+        &self,
+        reference_graph: &ControlFlowGraph,
+        sample_graphs: &[ControlFlowGraph],
+        is_self: bool,
+    ) -> Option<MethodMatch> {
+        let mut current_top: Option<(MethodMatch, &ControlFlowGraph)> = None;
+
+        for sample_graph in sample_graphs {
+            if self.is_cancelled() {
+                tracing::trace!(reference = %reference_graph.name, "comparison cancelled");
+                break;
+            }
+            if is_self && self.skip_self && sample_graph.offset == reference_graph.offset {
+                tracing::trace!(reference = %reference_graph.name, "skipped matching against itself");
+                continue;
+            }
+
+            if !self.passes_size_gate(reference_graph, sample_graph) {
+                tracing::trace!(
+                    reference = %reference_graph.name,
+                    sample = %sample_graph.name,
+                    "skipped by the size ratio gate",
+                );
+                continue;
+            }
+
+            let similarity: f32 = self.compare_graphs(reference_graph, sample_graph);
+            self.check_similarity(similarity, reference_graph, sample_graph);
+            // Check if the match if significant.
+            if similarity < self.threshold {
+                tracing::trace!(
+                    reference = %reference_graph.name,
+                    sample = %sample_graph.name,
+                    similarity,
+                    "match below threshold",
+                );
+                continue;
+            }
+
+            // If so, handle it.
+            let current_match = MethodMatch::new(sample_graph, reference_graph, similarity, self.confidence_scale);
+            if self.is_exact_match(similarity) {
+                tracing::trace!(reference = %reference_graph.name, sample = %sample_graph.name, "exact match");
+                current_top = Some((current_match, sample_graph));
+                break;
+            }
+
+            match current_top {
+                Some((ref top, _)) => {
+                    if similarity > top.similarity + self.epsilon {
+                        current_top = Some((current_match, sample_graph));
+                    }
+                }
+                None => {
+                    current_top = Some((current_match, sample_graph));
+                }
+            }
+        }
+
+        if current_top.is_none() {
+            tracing::trace!(reference = %reference_graph.name, "no significant match");
+        }
+
+        // Only align the blocks of the retained match, the alignment is costly to store.
+        current_top.map(|(top, sample_graph)| {
+            if self.block_alignment {
+                top.with_block_alignment(self.align_blocks(sample_graph, reference_graph))
+            } else {
+                top
+            }
+        })
+    }
+
+    // Whether a similarity is 1.0 within the grapher's epsilon, see `with_epsilon`.
+    fn is_exact_match(&self, similarity: f32) -> bool {
+        similarity >= 1.0 - self.epsilon
+    }
+
+    // In strict mode, record the first similarity that is not finite or outside of `[0, 1]`.
+    fn check_similarity(&self, similarity: f32, reference: &ControlFlowGraph, sample: &ControlFlowGraph) {
+        if !self.strict || (0.0..=1.0).contains(&similarity) {
+            return;
+        }
+
+        tracing::error!(reference = %reference.name, sample = %sample.name, similarity, "invalid similarity");
+        self.invalid_similarity
+            .lock()
+            .expect("Unexpected error while checking similarities")
+            .get_or_insert(Error::InvalidSimilarity {
+                reference: reference.name.clone(),
+                sample: sample.name.clone(),
+                similarity,
+            });
+    }
+
+    // Whether the block counts of two graphs are close enough to be compared, see `with_size_ratio_gate`.
+    fn passes_size_gate(&self, lhs: &ControlFlowGraph, rhs: &ControlFlowGraph) -> bool {
+        let Some(ratio) = self.size_ratio_gate else {
+            return true;
+        };
+        let (smaller, larger) = if lhs.blocks.len() <= rhs.blocks.len() {
+            (lhs.blocks.len(), rhs.blocks.len())
+        } else {
+            (rhs.blocks.len(), lhs.blocks.len())
+        };
+        larger == 0 || smaller as f32 >= ratio * larger as f32
+    }
+
+    // Align each block of `sample_graph` with its most similar block of `reference_graph`.
+    //
+    // Returns the (sample_block_index, reference_block_index, similarity) triplets, sample blocks
+    // are left out if the reference graph has no blocks.
+    fn align_blocks(
+        &self,
+        sample_graph: &ControlFlowGraph,
+        reference_graph: &ControlFlowGraph,
+    ) -> Vec<(usize, usize, f32)> {
+        let l_blocks: &[BasicBlock] = &sample_graph.blocks;
+        let r_blocks: &[BasicBlock] = &reference_graph.blocks;
+
+        if self.alignment == Alignment::Hungarian {
+            let similarities: Vec<Vec<f32>> = self.block_similarities(l_blocks, r_blocks);
+            let mut pairs: Vec<(usize, usize)> = Grapher::max_weight_assignment(&similarities);
+            pairs.sort_unstable();
+            return pairs
+                .into_iter()
+                .map(|(l_index, r_index)| (l_index, r_index, similarities[l_index][r_index]))
+                .collect();
+        }
+
+        (0..l_blocks.len())
+            .filter_map(|l_index| {
+                let mut best: Option<(usize, usize, f32)> = None;
+                for r_index in 0..r_blocks.len() {
+                    let similarity: f32 = self.compare_blocks(l_blocks, l_index, r_blocks, r_index);
+                    if best.is_none_or(|(_, _, top)| similarity > top) {
+                        best = Some((l_index, r_index, similarity));
+                    }
+                }
+                best
+            })
+            .collect()
+    }
+
+    // Compute the similarity of every pair of blocks, indexed by left then right block.
+    fn block_similarities(&self, l_blocks: &[BasicBlock], r_blocks: &[BasicBlock]) -> Vec<Vec<f32>> {
+        (0..l_blocks.len())
+            .map(|l_index| {
+                (0..r_blocks.len())
+                    .map(|r_index| self.compare_blocks(l_blocks, l_index, r_blocks, r_index))
+                    .collect()
+            })
+            .collect()
+    }
+
+    // Pair the rows and columns of a weight matrix one to one, maximizing the total weight.
+    //
+    // Solves the assignment problem with the Hungarian algorithm in `O(n^2 m)`, `n` being the
+    // smaller dimension. Returns `min(rows, columns)` (row, column) pairs.
+    fn max_weight_assignment(weights: &[Vec<f32>]) -> Vec<(usize, usize)> {
+        let rows: usize = weights.len();
+        let columns: usize = weights.first().map_or(0, Vec::len);
+        if rows == 0 || columns == 0 {
+            return Vec::new();
+        }
+
+        // The algorithm requires no more rows than columns, transpose the matrix otherwise.
+        let transposed: bool = rows > columns;
+        let (n, m) = if transposed { (columns, rows) } else { (rows, columns) };
+        let cost = |i: usize, j: usize| -> f64 {
+            let weight: f32 = if transposed { weights[j][i] } else { weights[i][j] };
+            -(weight as f64)
+        };
+
+        // Potentials and assignment, 1-indexed with the column 0 as a sentinel.
+        let mut u: Vec<f64> = vec![0.0; n + 1];
+        let mut v: Vec<f64> = vec![0.0; m + 1];
+        let mut assigned: Vec<usize> = vec![0; m + 1];
+        let mut way: Vec<usize> = vec![0; m + 1];
+        for i in 1..=n {
+            assigned[0] = i;
+            let mut j0: usize = 0;
+            let mut min_v: Vec<f64> = vec![f64::INFINITY; m + 1];
+            let mut used: Vec<bool> = vec![false; m + 1];
+            loop {
+                used[j0] = true;
+                let i0: usize = assigned[j0];
+                let mut delta: f64 = f64::INFINITY;
+                let mut j1: usize = 0;
+                for j in 1..=m {
+                    if used[j] {
+                        continue;
+                    }
+                    let current: f64 = cost(i0 - 1, j - 1) - u[i0] - v[j];
+                    if current < min_v[j] {
+                        min_v[j] = current;
+                        way[j] = j0;
+                    }
+                    if min_v[j] < delta {
+                        delta = min_v[j];
+                        j1 = j;
+                    }
+                }
+                for j in 0..=m {
+                    if used[j] {
+                        u[assigned[j]] += delta;
+                        v[j] -= delta;
+                    } else {
+                        min_v[j] -= delta;
+                    }
+                }
+                j0 = j1;
+                if assigned[j0] == 0 {
+                    break;
+                }
+            }
+            // Augment along the alternating path.
+            loop {
+                let j1: usize = way[j0];
+                assigned[j0] = assigned[j1];
+                j0 = j1;
+                if j0 == 0 {
+                    break;
+                }
+            }
+        }
+
+        (1..=m)
+            .filter(|&j| assigned[j] != 0)
+            .map(|j| {
+                let (i, j) = (assigned[j] - 1, j - 1);
+                if transposed { (j, i) } else { (i, j) }
+            })
+            .collect()
+    }
+
+    // Compare two control flow graphs.
+    fn compare_graph_sets(
+        &self,
+        sample_graphs: &Disassembly,
+        reference_graphs: &Disassembly,
+    ) -> BinaryMatch {
+        // Disassemblies of the same binary share their fingerprint, even when loaded separately, while
+        // unrelated ones may share a path, such as the in-memory subsets and signature databases.
+        let is_self: bool = self.skip_self
+            && (std::ptr::eq(sample_graphs, reference_graphs)
+                || sample_graphs.fingerprint() == reference_graphs.fingerprint());
+        let reference_count: usize = self
+            .max_reference_graphs
+            .map_or(reference_graphs.graphs.len(), |max| max.min(reference_graphs.graphs.len()));
+        self.compare_graph_slices(
+            &sample_graphs.graphs,
+            &reference_graphs.graphs[..reference_count],
+            (&sample_graphs.name, &reference_graphs.name),
+            is_self,
+        )
+    }
+
+    /// Compare two lists of Control Flow Graphs (CFG) and produce their matching pairs.
+    ///
+    /// The `names` are the names of the sample and reference the graphs belong to. Both lists are
+    /// expected to be normalized with the grapher's mode. When `is_self` is set both lists come from
+    /// the same binary, a function is then never matched against the graph at its own offset if
+    /// `skip_self` is enabled. Only fails in strict mode, see `compare`.
+    pub fn compare_graph_lists(
+        &self,
+        sample_graphs: &[ControlFlowGraph],
+        reference_graphs: &[ControlFlowGraph],
+        names: (&str, &str),
+        is_self: bool,
+    ) -> Result<BinaryMatch, Error> {
+        let grapher: Grapher = self.checked();
+        let matches: BinaryMatch = grapher.compare_graph_slices(sample_graphs, reference_graphs, names, is_self);
+        grapher.strict_result(matches)
+    }
+
+    // Compare two lists of control flow graphs.
+    fn compare_graph_slices(
+        &self,
+        sample_graphs: &[ControlFlowGraph],
+        reference_graphs: &[ControlFlowGraph],
+        (sample_name, reference_name): (&str, &str),
+        is_self: bool,
+    ) -> BinaryMatch {
+        let compute_start: Instant = Instant::now();
+        let mut progress_bar: Arc<Option<ProgressBar>> = Arc::new(None);
+
+        if let Some(multiprogress) = self.multiprogress.clone().deref() {
+            let new_progress_bar: ProgressBar = multiprogress.add(
+                ProgressBar::new(reference_graphs.len() as u64)
+            );
+            new_progress_bar.set_style(self.bar_style(&self.progress_theme.reference_template));
+            progress_bar = Arc::new(Some(new_progress_bar));
+        }
+
+        let matches: Vec<_> = reference_graphs
+            .par_iter()
+            .filter_map(|reference_graph| {
+                if self.is_cancelled() {
+                    return None;
+                }
+
+                let progress: Arc<Option<ProgressBar>> = progress_bar.clone();
+                if let Some(progress_bar) = progress.deref() {
+                    progress_bar.set_message(format!("Matching {reference_name}"));
+                }
+
+                let current_match = self.compare_against_graphs(reference_graph, sample_graphs, is_self);
+
+                if let Some(progress_bar) = progress.deref() {
+                    progress_bar.inc(1);
+                    if progress_bar.position() >= progress_bar.length().expect("Progress bar's length not set") {
+                        progress_bar.finish_and_clear();
+                    }
                 }
 
-                current_match
-            })
-            .collect();
+                current_match
+            })
+            .collect();
+
+        // Cancelled comparisons never reach the end of the progress bar.
+        if let Some(progress_bar) = progress_bar.deref() {
+            progress_bar.finish_and_clear();
+        }
+
+        BinaryMatch::new(
+            sample_name,
+            reference_name,
+            &matches,
+            sample_graphs.len(),
+            reference_graphs.len(),
+        )
+        .with_compute_time(compute_start.elapsed())
+        .with_floor(self.report_floor)
+    }
+}
+
+#[pymethods]
+impl Grapher {
+    #[new]
+    #[pyo3(signature = (
+        *,
+        threshold,
+        display_progress,
+        mask_immediates=false,
+        skip_self=false,
+        pooling="mean",
+        top_k=None,
+        confidence_scale=DEFAULT_CONFIDENCE_SCALE,
+        block_alignment=false,
+        fuzzy_bytes=false,
+        progress_mode=None,
+        use_call_targets=false,
+        fast_block_overlap=false,
+        cross_arch=false,
+        alignment="greedy",
+        min_block_instructions=0,
+        size_ratio_gate=None,
+        checkpoint_path=None,
+        graph_norm="min",
+        strict=false,
+        epsilon=DEFAULT_EPSILON,
+        progress_style="default",
+        report_floor=0.0,
+        strip_nops=false,
+        prefix_blocks=None,
+        bloom_prefilter=None,
+        max_reference_graphs=None,
+        chunk_size=None,
+        block_weighting="uniform"
+    ))]
+    #[allow(clippy::too_many_arguments)]
+    fn py_new(
+        threshold: f32,
+        display_progress: bool,
+        mask_immediates: bool,
+        skip_self: bool,
+        pooling: &str,
+        top_k: Option<usize>,
+        confidence_scale: f32,
+        block_alignment: bool,
+        fuzzy_bytes: bool,
+        progress_mode: Option<&str>,
+        use_call_targets: bool,
+        fast_block_overlap: bool,
+        cross_arch: bool,
+        alignment: &str,
+        min_block_instructions: usize,
+        size_ratio_gate: Option<f32>,
+        checkpoint_path: Option<PathBuf>,
+        graph_norm: &str,
+        strict: bool,
+        epsilon: f32,
+        progress_style: &str,
+        report_floor: f32,
+        strip_nops: bool,
+        prefix_blocks: Option<usize>,
+        bloom_prefilter: Option<f32>,
+        max_reference_graphs: Option<usize>,
+        chunk_size: Option<usize>,
+        block_weighting: &str,
+        py: Python
+    ) -> PyResult<Self> {
+        let pooling: Pooling = match (pooling, top_k.and_then(NonZeroUsize::new)) {
+            ("mean", _) => Pooling::Mean,
+            ("max", _) => Pooling::Max,
+            ("top_k", Some(k)) => Pooling::TopK(k),
+            ("top_k", None) => return Err(PyValueError::new_err("top_k pooling requires a positive `top_k`.")),
+            (other, _) => return Err(PyValueError::new_err(format!("Unknown pooling strategy {other:?}."))),
+        };
+        let progress_mode: ProgressMode = match progress_mode {
+            None if display_progress => ProgressMode::Bar,
+            None | Some("none") => ProgressMode::None,
+            Some("bar") => ProgressMode::Bar,
+            Some("log") => ProgressMode::Log,
+            Some(other) => return Err(PyValueError::new_err(format!("Unknown progress mode {other:?}."))),
+        };
+        let progress_theme: ProgressTheme = match progress_style {
+            "default" => ProgressTheme::default(),
+            "ascii" => ProgressTheme::ascii(),
+            other => return Err(PyValueError::new_err(format!("Unknown progress style {other:?}."))),
+        };
+        let alignment: Alignment = match alignment {
+            "greedy" => Alignment::Greedy,
+            "hungarian" => Alignment::Hungarian,
+            other => return Err(PyValueError::new_err(format!("Unknown alignment {other:?}."))),
+        };
+        if !(0.0..=1.0).contains(&threshold) {
+            return Err(PyValueError::new_err(format!(
+                "`threshold` must be within [0, 1], got {threshold}."
+            )));
+        }
+        let graph_norm: GraphNorm = match graph_norm {
+            "min" => GraphNorm::Min,
+            "max" => GraphNorm::Max,
+            "mean" => GraphNorm::Mean,
+            other => return Err(PyValueError::new_err(format!("Unknown graph norm {other:?}."))),
+        };
+        let block_weighting: BlockWeighting = match block_weighting {
+            "uniform" => BlockWeighting::Uniform,
+            "instruction_count" => BlockWeighting::InstructionCount,
+            other => return Err(PyValueError::new_err(format!("Unknown block weighting {other:?}."))),
+        };
+        if prefix_blocks == Some(0) {
+            return Err(PyValueError::new_err("`prefix_blocks` must be positive."));
+        }
+        if chunk_size == Some(0) {
+            return Err(PyValueError::new_err("`chunk_size` must be positive."));
+        }
+
+        let thread_handle: thread::JoinHandle<Result<Self, Error>> = thread::spawn(move || {
+            Ok(Grapher::new(threshold, display_progress)
+                .with_mask_immediates(mask_immediates)
+                .with_call_targets(use_call_targets)
+                .with_cross_arch(cross_arch)
+                .with_min_block_instructions(min_block_instructions)
+                .with_strip_nops(strip_nops)
+                .with_skip_self(skip_self)
+                .with_pooling(pooling)
+                .with_confidence_scale(confidence_scale)?
+                .with_block_alignment(block_alignment)
+                .with_fuzzy_bytes(fuzzy_bytes)
+                .with_progress_mode(progress_mode)
+                .with_progress_theme(progress_theme)
+                .with_fast_block_overlap(fast_block_overlap)
+                .with_alignment(alignment)
+                .with_size_ratio_gate(size_ratio_gate)?
+                .with_checkpoint_path(checkpoint_path)
+                .with_graph_norm(graph_norm)
+                .with_block_weighting(block_weighting)
+                .with_strict(strict)
+                .with_epsilon(epsilon)?
+                .with_report_floor(report_floor)?
+                .with_prefix_blocks(prefix_blocks.and_then(NonZeroUsize::new))
+                .with_bloom_prefilter(bloom_prefilter)?
+                .with_max_reference_graphs(max_reference_graphs)
+                .with_chunk_size(chunk_size.and_then(NonZeroUsize::new)))
+        });
+
+        loop {
+            if py.check_signals().is_err() {
+                break Err(
+                    PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
+                );
+            }
+            if thread_handle.is_finished() {
+                break Ok(thread_handle.join().unwrap()?);
+            }
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
+        }
+    }
+
+    #[pyo3(name = "compare", signature = (sample_graph, reference_graphs, *, name=None))]
+    fn py_compare(
+        &self,
+        sample_graph: Py<Disassembly>,
+        reference_graphs: Vec<Py<Disassembly>>,
+        name: Option<String>,
+        py: Python
+    ) -> PyResult<CompareReport> {
+        let grapher = self.clone();
+
+        // The disassemblies are frozen, the thread borrows them through their handles rather than
+        // cloning them, which would double the memory held by large reference sets.
+        let thread_handle: thread::JoinHandle<Result<CompareReport, Error>> = thread::spawn(move || {
+            let mut report: CompareReport = grapher.compare(sample_graph.get(), reference_graphs.iter().map(Py::get).collect())?;
+            if let Some(name) = name {
+                report.set_sample_name(&name);
+            }
+            Ok(report)
+        });
+
+        loop {
+            if py.check_signals().is_err() {
+                // Stop the running thread too, the clone it holds shares the cancellation flag.
+                self.cancel();
+                break Err(
+                    PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
+                );
+            }
+            if thread_handle.is_finished() {
+                break Ok(thread_handle.join().unwrap()?);
+            }
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
+        }
+    }
+
+    #[pyo3(name = "compare_streaming")]
+    fn py_compare_streaming(
+        &self,
+        sample_graph: Py<Disassembly>,
+        reference_graphs: Vec<Py<Disassembly>>,
+        callback: Py<PyAny>,
+        py: Python
+    ) -> PyResult<()> {
+        let grapher = self.clone();
+        let (sender, receiver) = mpsc::channel::<BinaryMatch>();
+
+        let thread_handle: thread::JoinHandle<Result<(), Error>> = thread::spawn(move || {
+            grapher.compare_streaming(sample_graph.get(), reference_graphs.iter().map(Py::get).collect(), |matches| {
+                let _ = sender.send(matches);
+            })
+        });
+
+        loop {
+            if py.check_signals().is_err() {
+                // Stop the running thread too, the clone it holds shares the cancellation flag.
+                self.cancel();
+                break Err(
+                    PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
+                );
+            }
+            // Check for completion first so that no match is left behind in the channel.
+            let is_finished: bool = thread_handle.is_finished();
+            for matches in receiver.try_iter() {
+                callback.call1(py, (matches,))?;
+            }
+            if is_finished {
+                break Ok(thread_handle.join().unwrap()?);
+            }
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
+        }
+    }
+
+    #[pyo3(name = "compare_lazy", signature = (sample_graph, reference_paths, *, batch_size=1, name=None))]
+    fn py_compare_lazy(
+        &self,
+        sample_graph: Py<Disassembly>,
+        reference_paths: Vec<PathBuf>,
+        batch_size: usize,
+        name: Option<String>,
+        py: Python
+    ) -> PyResult<CompareReport> {
+        if batch_size == 0 {
+            return Err(PyValueError::new_err("`batch_size` must be positive."));
+        }
+        let grapher = self.clone();
+
+        let thread_handle: thread::JoinHandle<Result<CompareReport, Error>> = thread::spawn(move || {
+            let mut report: CompareReport = grapher.compare_lazy(sample_graph.get(), &reference_paths, batch_size)?;
+            if let Some(name) = name {
+                report.set_sample_name(&name);
+            }
+            Ok(report)
+        });
 
-        BinaryMatch::new(&sample_graphs.name, &reference_graphs.name, &matches)
+        loop {
+            if py.check_signals().is_err() {
+                // Stop the running thread too, the clone it holds shares the cancellation flag.
+                self.cancel();
+                break Err(
+                    PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
+                );
+            }
+            if thread_handle.is_finished() {
+                break Ok(thread_handle.join().unwrap()?);
+            }
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
+        }
     }
-}
 
-#[pymethods]
-impl Grapher {
-    #[new]
-    #[pyo3(signature = (*, threshold, display_progress))]
-    fn py_new(
-        threshold: f32,
-        display_progress: bool,
+    #[pyo3(name = "compare_to_db", signature = (sample_graph, db, *, name=None))]
+    fn py_compare_to_db(
+        &self,
+        sample_graph: Py<Disassembly>,
+        db: Py<SignatureDb>,
+        name: Option<String>,
         py: Python
-    ) -> PyResult<Self> {
-        let thread_handle: thread::JoinHandle<Self> = thread::spawn(move || {
-            Grapher::new(threshold, display_progress)
+    ) -> PyResult<CompareReport> {
+        let grapher = self.clone();
+
+        // The sample and database are frozen, the thread borrows them through their handles as in `compare`.
+        let thread_handle: thread::JoinHandle<Result<CompareReport, Error>> = thread::spawn(move || {
+            let mut report: CompareReport = grapher.compare_to_db(sample_graph.get(), db.get())?;
+            if let Some(name) = name {
+                report.set_sample_name(&name);
+            }
+            Ok(report)
         });
 
         loop {
             if py.check_signals().is_err() {
+                // Stop the running thread too, the clone it holds shares the cancellation flag.
+                self.cancel();
                 break Err(
                     PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
                 );
             }
             if thread_handle.is_finished() {
-                break Ok(thread_handle.join().unwrap());
+                break Ok(thread_handle.join().unwrap()?);
             }
-            thread::sleep(Duration::from_millis(1));
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
         }
     }
 
-    #[pyo3(name = "compare")]
-    fn py_compare(
+    #[pyo3(name = "config")]
+    fn py_config(&self) -> CompareConfig {
+        self.config()
+    }
+
+    #[pyo3(name = "cancel")]
+    fn py_cancel(&self) {
+        self.cancel()
+    }
+
+    #[pyo3(name = "is_cancelled")]
+    fn py_is_cancelled(&self) -> bool {
+        self.is_cancelled()
+    }
+
+    #[pyo3(name = "reset_cancel")]
+    fn py_reset_cancel(&self) {
+        self.reset_cancel()
+    }
+
+    #[pyo3(name = "fit_idf")]
+    fn py_fit_idf(&mut self, reference_graphs: Vec<PyRef<Disassembly>>) {
+        self.fit_idf(&reference_graphs)
+    }
+
+    #[pyo3(name = "clear_idf")]
+    fn py_clear_idf(&mut self) {
+        self.clear_idf()
+    }
+
+    #[pyo3(name = "compare_graph_lists", signature = (sample_graphs, reference_graphs, names, *, is_self=false))]
+    fn py_compare_graph_lists(
         &self,
-        sample_graph: PyRef<Disassembly>,
-        reference_graphs: Vec<PyRef<Disassembly>>,
+        sample_graphs: Vec<PyRef<ControlFlowGraph>>,
+        reference_graphs: Vec<PyRef<ControlFlowGraph>>,
+        names: (String, String),
+        is_self: bool,
         py: Python
-    ) -> PyResult<CompareReport> {
+    ) -> PyResult<BinaryMatch> {
         let grapher = self.clone();
-        let sample_ref: Disassembly = sample_graph.deref().clone();
-        let disassemblies: Vec<Disassembly> = reference_graphs.iter().map(|graph| {
+        let mut samples: Vec<ControlFlowGraph> = sample_graphs.iter().map(|graph| {
             graph.deref().clone()
         }).collect();
+        let mut references: Vec<ControlFlowGraph> = reference_graphs.iter().map(|graph| {
+            graph.deref().clone()
+        }).collect();
+
+        let thread_handle: thread::JoinHandle<Result<BinaryMatch, Error>> = thread::spawn(move || {
+            for graph in samples.iter_mut().chain(references.iter_mut()) {
+                graph.normalize(grapher.normalization);
+            }
+            grapher.compare_graph_lists(&samples, &references, (&names.0, &names.1), is_self)
+        });
+
+        loop {
+            if py.check_signals().is_err() {
+                // Stop the running thread too, the clone it holds shares the cancellation flag.
+                self.cancel();
+                break Err(
+                    PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
+                );
+            }
+            if thread_handle.is_finished() {
+                break Ok(thread_handle.join().unwrap()?);
+            }
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
+        }
+    }
+
+    #[pyo3(name = "compare_many")]
+    fn py_compare_many(
+        &self,
+        sample_graphs: Vec<Py<Disassembly>>,
+        reference_graphs: Vec<Py<Disassembly>>,
+        py: Python
+    ) -> PyResult<Vec<CompareReport>> {
+        let grapher = self.clone();
+
+        let thread_handle: thread::JoinHandle<Result<Vec<CompareReport>, Error>> = thread::spawn(move || {
+            grapher.compare_many(
+                sample_graphs.iter().map(Py::get).collect(),
+                reference_graphs.iter().map(Py::get).collect(),
+            )
+        });
+
+        loop {
+            if py.check_signals().is_err() {
+                // Stop the running thread too, the clone it holds shares the cancellation flag.
+                self.cancel();
+                break Err(
+                    PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
+                );
+            }
+            if thread_handle.is_finished() {
+                break Ok(thread_handle.join().unwrap()?);
+            }
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
+        }
+    }
+
+    #[pyo3(name = "compare_report_into")]
+    fn py_compare_report_into(
+        &self,
+        sample_graph: Py<Disassembly>,
+        reference_graphs: Vec<Py<Disassembly>>,
+        mut report: PyRefMut<CompareReport>,
+        py: Python
+    ) -> PyResult<()> {
+        let grapher = self.clone();
+        let mut target: CompareReport = report.clone();
+
+        let thread_handle: thread::JoinHandle<Result<CompareReport, Error>> = thread::spawn(move || {
+            grapher.compare_report_into(sample_graph.get(), reference_graphs.iter().map(Py::get).collect(), &mut target)?;
+            Ok(target)
+        });
+
+        loop {
+            if py.check_signals().is_err() {
+                // Stop the running thread too, the clone it holds shares the cancellation flag.
+                self.cancel();
+                break Err(
+                    PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
+                );
+            }
+            if thread_handle.is_finished() {
+                *report = thread_handle.join().unwrap()?;
+                break Ok(());
+            }
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
+        }
+    }
+
+    #[pyo3(name = "graph_similarity")]
+    fn py_graph_similarity(&self, lhs: PyRef<ControlFlowGraph>, rhs: PyRef<ControlFlowGraph>) -> PyResult<f32> {
+        Ok(self.graph_similarity(&lhs, &rhs)?)
+    }
+
+    #[pyo3(name = "block_similarity_matrix")]
+    fn py_block_similarity_matrix(&self, lhs: PyRef<ControlFlowGraph>, rhs: PyRef<ControlFlowGraph>) -> Vec<Vec<f32>> {
+        self.block_similarity_matrix(&lhs, &rhs)
+    }
+
+    #[pyo3(name = "score_pairs")]
+    fn py_score_pairs(
+        &self,
+        sample: PyRef<Disassembly>,
+        reference: PyRef<Disassembly>,
+        pairs: Vec<(u64, u64)>,
+    ) -> PyResult<Vec<(u64, u64, f32)>> {
+        Ok(self.score_pairs(&sample, &reference, &pairs)?)
+    }
+
+    #[pyo3(name = "diff_methods")]
+    fn py_diff_methods(&self, source: PyRef<ControlFlowGraph>, target: PyRef<ControlFlowGraph>) -> MethodDiff {
+        self.diff_methods(&source, &target)
+    }
+
+    #[pyo3(name = "analyze")]
+    fn analyze_py(
+        &self,
+        sample_path: PathBuf,
+        reference_paths: Vec<(String, PathBuf)>,
+        py: Python
+    ) -> PyResult<CompareReport> {
+        let grapher = self.clone();
+
+        let thread_handle: thread::JoinHandle<Result<CompareReport, Error>> = thread::spawn(move || {
+            grapher.analyze(&sample_path, &reference_paths)
+        });
+
+        loop {
+            if py.check_signals().is_err() {
+                // Stop the running thread too, the clone it holds shares the cancellation flag.
+                self.cancel();
+                break Err(
+                    PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
+                );
+            }
+            if thread_handle.is_finished() {
+                break Ok(thread_handle.join().unwrap()?);
+            }
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
+        }
+    }
+
+    #[pyo3(name = "analyze_many")]
+    fn analyze_many_py(
+        &self,
+        sample_paths: Vec<PathBuf>,
+        reference_paths: Vec<(String, PathBuf)>,
+        py: Python
+    ) -> PyResult<Vec<CompareReport>> {
+        let grapher = self.clone();
 
-        let thread_handle: thread::JoinHandle<CompareReport> = thread::spawn(move || {
-            grapher.compare(&sample_ref, disassemblies.iter().collect())
+        let thread_handle: thread::JoinHandle<Result<Vec<CompareReport>, Error>> = thread::spawn(move || {
+            grapher.analyze_many(&sample_paths, &reference_paths)
         });
 
         loop {
             if py.check_signals().is_err() {
+                // Stop the running thread too, the clone it holds shares the cancellation flag.
+                self.cancel();
                 break Err(
                     PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
                 );
             }
             if thread_handle.is_finished() {
-                break Ok(thread_handle.join().unwrap());
+                break Ok(thread_handle.join().unwrap()?);
             }
-            thread::sleep(Duration::from_millis(1));
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
         }
     }
 
@@ -433,6 +2464,8 @@ impl Grapher {
 
         loop {
             if py.check_signals().is_err() {
+                // Stop the running thread too, the clone it holds shares the cancellation flag.
+                self.cancel();
                 break Err(
                     PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
                 );
@@ -440,7 +2473,536 @@ impl Grapher {
             if thread_handle.is_finished() {
                 break Ok(thread_handle.join().unwrap()?);
             }
-            thread::sleep(Duration::from_millis(1));
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
+        }
+    }
+
+    #[pyo3(name = "generate_graphs_lenient")]
+    fn generate_graphs_lenient_py(
+        &self,
+        sample_list: Vec<(String, PathBuf)>,
+        py: Python
+    ) -> PyResult<(Vec<Disassembly>, Vec<PyGraphFailure>)> {
+        let grapher = self.clone();
+
+        let thread_handle: thread::JoinHandle<LenientGraphs> = thread::spawn(move || {
+            grapher.generate_graphs_lenient(&sample_list)
+        });
+
+        loop {
+            if py.check_signals().is_err() {
+                // Stop the running thread too, the clone it holds shares the cancellation flag.
+                self.cancel();
+                break Err(
+                    PyKeyboardInterrupt::new_err("Rust: received ctrl-c.")
+                );
+            }
+            if thread_handle.is_finished() {
+                let (samples_graph, failures) = thread_handle.join().unwrap();
+                let failures: Vec<PyGraphFailure> = failures
+                    .into_iter()
+                    .map(|(path, error)| (path, PyErr::from(error).into_value(py)))
+                    .collect();
+                break Ok((samples_graph, failures));
+            }
+            py.allow_threads(|| thread::sleep(Duration::from_millis(1)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use smda::function::Instruction;
+
+    use super::*;
+    use crate::test_utils::{block, chain_graph, disassembly, instruction, keyed_block};
+
+    // A sample and a reference whose single functions differ, so that their similarity is computed.
+    fn sample_and_reference() -> (Disassembly, Disassembly) {
+        let sample = chain_graph("main.f", 0x1000, vec![keyed_block(0x1000, &["55", "90"]), keyed_block(0x1002, &["c3"])]);
+        let reference = chain_graph("main.f", 0x2000, vec![keyed_block(0x2000, &["55", "cc"]), keyed_block(0x2002, &["c3"])]);
+        (disassembly("sample", vec![sample]), disassembly("reference", vec![reference]))
+    }
+
+    // Collect the keys streamed over the blocks at `indices`, along with the streamer's length.
+    fn streamed(blocks: &[BasicBlock], indices: &[usize]) -> (Vec<String>, usize) {
+        let streamer: InstructionStreamer = InstructionStreamer::new(blocks, indices);
+        (streamer.iter().cloned().collect(), streamer.len())
+    }
+
+    #[test]
+    fn instruction_streamer_chains_blocks() {
+        let blocks: Vec<BasicBlock> = vec![
+            keyed_block(0x0, &["55", "4889e5"]),
+            keyed_block(0x4, &[]),
+            keyed_block(0x4, &["90"]),
+            keyed_block(0x5, &["e8", "c9", "c3"]),
+        ];
+
+        // Blocks of varying lengths, an empty block and out of range indices, such as past a prefix.
+        let (keys, len) = streamed(&blocks, &[0, 1, 9, 3, 2, 4]);
+        assert_eq!(keys, ["55", "4889e5", "e8", "c9", "c3", "90"]);
+        assert_eq!(len, keys.len());
+
+        let (keys, len) = streamed(&blocks, &[7, 8]);
+        assert!(keys.is_empty());
+        assert_eq!(len, 0);
+    }
+
+    #[test]
+    fn instruction_streamer_skips_empty_blocks() {
+        let blocks: Vec<BasicBlock> = vec![
+            keyed_block(0x0, &[]),
+            keyed_block(0x0, &["55"]),
+            keyed_block(0x1, &[]),
+            keyed_block(0x1, &[]),
+            keyed_block(0x1, &["90", "c3"]),
+            keyed_block(0x3, &[]),
+        ];
+
+        // Leading, consecutive and trailing empty blocks neither end the stream nor yield keys.
+        let (keys, len) = streamed(&blocks, &[0, 1, 2, 3, 4, 5]);
+        assert_eq!(keys, ["55", "90", "c3"]);
+        assert_eq!(len, 3);
+
+        let (keys, len) = streamed(&blocks, &[0, 2, 5]);
+        assert!(keys.is_empty());
+        assert_eq!(len, 0);
+    }
+
+    #[test]
+    fn strict_mode_returns_invalid_similarity() {
+        let (valid, reference) = sample_and_reference();
+        // A function without blocks pools no block similarity at all, yielding a NaN similarity.
+        let sample: Disassembly = disassembly("sample", vec![chain_graph("main.f", 0x1000, Vec::new())]);
+        let grapher: Grapher = Grapher::new(0.0, false).with_strict(true);
+        let is_invalid = |error: Error| matches!(error, Error::InvalidSimilarity { .. });
+
+        assert!(grapher.compare(&sample, vec![&reference]).is_err_and(is_invalid));
+        assert!(grapher.compare_many(vec![&sample], vec![&reference]).is_err_and(is_invalid));
+        assert!(grapher.compare_streaming(&sample, vec![&reference], |_| {}).is_err_and(is_invalid));
+        assert!(grapher
+            .compare_graph_lists(&sample.graphs, &reference.graphs, ("sample", "reference"), false)
+            .is_err_and(is_invalid));
+        assert!(grapher.graph_similarity(&sample.graphs[0], &reference.graphs[0]).is_err_and(is_invalid));
+        assert!(grapher.score_pairs(&sample, &reference, &[(0x1000, 0x2000)]).is_err_and(is_invalid));
+
+        // The failure of a call does not leak into the next ones.
+        let lenient: Grapher = grapher.clone().with_strict(false);
+        assert!(lenient.compare(&sample, vec![&reference]).is_ok());
+        assert!(grapher.compare(&valid, vec![&reference]).is_ok());
+    }
+
+    // The pooling of the best `k` block similarities.
+    fn top_k(k: usize) -> Pooling {
+        Pooling::TopK(NonZeroUsize::new(k).unwrap())
+    }
+
+    // A graph chaining blocks of the given keys, laid out one byte per key from `offset`.
+    fn keyed_graph(name: &str, offset: u64, blocks: &[&[&str]]) -> ControlFlowGraph {
+        let mut block_offset: u64 = offset;
+        let blocks: Vec<BasicBlock> = blocks
+            .iter()
+            .map(|keys| {
+                let block: BasicBlock = keyed_block(block_offset, keys);
+                block_offset += keys.len() as u64;
+                block
+            })
+            .collect();
+        chain_graph(name, offset, blocks)
+    }
+
+    #[test]
+    fn chunked_similarities_match_unchunked() {
+        let large = keyed_graph("main.large", 0x1000, &[
+            &["55", "4889e5"], &["90"], &["e8", "c3"], &["31c0", "c3"], &["cc"], &["55", "c3", "90"], &["0f0b"],
+        ]);
+        let small = keyed_graph("main.small", 0x2000, &[&["55", "4889e5"], &["e8", "cc"], &["31c0"], &["c3"]]);
+
+        for pooling in [Pooling::Mean, Pooling::Max, top_k(1), top_k(3), top_k(9)] {
+            for block_weighting in [BlockWeighting::Uniform, BlockWeighting::InstructionCount] {
+                let grapher: Grapher = Grapher::new(0.0, false)
+                    .with_pooling(pooling)
+                    .with_block_weighting(block_weighting);
+                // Windows smaller, uneven and larger than either graph, with a larger and smaller reference.
+                for (reference, sample) in [(&large, &small), (&small, &large)] {
+                    let unchunked: f32 = grapher.graph_similarity(reference, sample).unwrap();
+                    for chunk_size in [1, 2, 3, 64] {
+                        let chunked: f32 = grapher
+                            .clone()
+                            .with_chunk_size(NonZeroUsize::new(chunk_size))
+                            .graph_similarity(reference, sample)
+                            .unwrap();
+                        assert_eq!(
+                            chunked.to_bits(),
+                            unchunked.to_bits(),
+                            "{pooling:?} {block_weighting:?} {} chunk_size={chunk_size}",
+                            reference.name,
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn compare_graph_lists_skips_self_matches() {
+        let graphs: Vec<ControlFlowGraph> = vec![
+            keyed_graph("main.f", 0x1000, &[&["55", "4889e5"], &["c3"]]),
+            keyed_graph("main.g", 0x2000, &[&["55", "4889e5"], &["90", "c3"]]),
+        ];
+        // Separate copies of the same binary's graphs, as handed over by the Python binding.
+        let copies: Vec<ControlFlowGraph> = graphs.clone();
+        let grapher: Grapher = Grapher::new(0.5, false).with_skip_self(true);
+        let pairs = |is_self: bool| -> Vec<(u64, u64)> {
+            let matches: BinaryMatch = grapher.compare_graph_lists(&graphs, &copies, ("bin", "bin"), is_self).unwrap();
+            matches.matches().iter().map(|method| (method.malware_offset(), method.clean_offset())).collect()
+        };
+
+        assert!(pairs(false).iter().any(|(sample, reference)| sample == reference));
+        let clones: Vec<(u64, u64)> = pairs(true);
+        assert!(!clones.is_empty());
+        assert!(clones.iter().all(|(sample, reference)| sample != reference));
+    }
+
+    #[test]
+    fn mask_immediates_matches_relocated_copies() {
+        // The same function built twice, with a different constant and a relocated call target.
+        let function = |name: &str, offset: u64, constant: (&str, &str), call: (&str, &str)| -> ControlFlowGraph {
+            let entry: &[(&str, &str, &str)] =
+                &[("55", "push", "rbp"), (constant.0, "mov", constant.1), (call.0, "call", call.1)];
+            let exit: &[(&str, &str, &str)] = &[("c3", "ret", "")];
+            chain_graph(name, offset, vec![block(offset, entry, (&[], &[])), block(offset + 0x10, exit, (&[], &[]))])
+        };
+        let reference = function("lib.f", 0x1000, ("b878563412", "eax, 0x12345678"), ("e8f93f0000", "0x5000"));
+        let sample = function("main.f", 0x2000, ("b800100000", "eax, 0x1000"), ("e8f94f0000", "0x7000"));
+        let similarity = |mask_immediates: bool| -> f32 {
+            let grapher: Grapher = Grapher::new(0.0, false).with_mask_immediates(mask_immediates);
+            grapher.graph_similarity(&reference, &sample).unwrap()
+        };
+
+        assert!(similarity(false) < 1.0);
+        assert_eq!(similarity(true), 1.0);
+    }
+
+    #[test]
+    fn skip_self_ignores_shared_paths() {
+        let graph = keyed_graph("main.f", 0x1000, &[&["55", "4889e5"], &["c3"]]);
+        let sample: Disassembly = disassembly("bin", vec![graph.clone()]);
+        // A different binary at the same path, holding the same function at the same offset.
+        let other: Disassembly =
+            disassembly("bin", vec![graph.clone(), keyed_graph("main.g", 0x2000, &[&["90", "c3"]])]);
+        let grapher: Grapher = Grapher::new(0.5, false).with_skip_self(true);
+        let pairs = |reference: &Disassembly| -> Vec<(u64, u64)> {
+            let report: CompareReport = grapher.compare(&sample, vec![reference]).unwrap();
+            report_pairs(&report).into_iter().map(|(_, sample, reference, _)| (sample, reference)).collect()
+        };
+
+        assert!(pairs(&sample).is_empty());
+        assert!(pairs(&disassembly("copy", vec![graph])).is_empty());
+        assert!(pairs(&other).contains(&(0x1000, 0x1000)));
+    }
+
+    // The (sample offset, reference offset, similarity bits) of every function match of a report.
+    fn report_pairs(report: &CompareReport) -> Vec<(String, u64, u64, u32)> {
+        let mut pairs: Vec<(String, u64, u64, u32)> = report
+            .matches()
+            .iter()
+            .flat_map(|binary| {
+                binary.matches().iter().map(|method| {
+                    let similarity: u32 = method.similarity().to_bits();
+                    (binary.dest().clone(), method.malware_offset(), method.clean_offset(), similarity)
+                })
+            })
+            .collect();
+        pairs.sort();
+        pairs
+    }
+
+    #[test]
+    fn similarity_cache_matches_uncached_report() {
+        let bodies: [&[&[&str]]; 3] = [
+            &[&["55", "4889e5"], &["e8", "c3"]],
+            &[&["55", "31c0"], &["90"], &["c3"]],
+            &[&["4883ec08"], &["e8", "cc"], &["c3"]],
+        ];
+        // Every body is duplicated at several offsets of the sample and references, alongside
+        // near duplicates whose hashes differ.
+        let graphs = |name: &str, base: u64, extra: &str| -> Vec<ControlFlowGraph> {
+            (0..12u64)
+                .map(|index| {
+                    let offset: u64 = base + index * 0x100;
+                    match index % 4 {
+                        3 => keyed_graph(&format!("{name}.near{index}"), offset, &[&["55", extra], &["c3"]]),
+                        body => keyed_graph(&format!("{name}.f{index}"), offset, bodies[body as usize]),
+                    }
+                })
+                .collect()
+        };
+        let sample: Disassembly = disassembly("sample", graphs("main", 0x1000, "90"));
+        let references: Vec<Disassembly> = vec![
+            disassembly("first", graphs("lib", 0x10000, "cc")),
+            disassembly("second", graphs("lib", 0x20000, "4889e5")),
+        ];
+
+        let grapher: Grapher = Grapher::new(0.1, false);
+        let cached: CompareReport = grapher.compare(&sample, references.iter().collect()).unwrap();
+        let uncached: CompareReport = grapher.checked().compare_cached(&sample, references.iter().collect());
+        assert!(!report_pairs(&cached).is_empty());
+        assert_eq!(report_pairs(&cached), report_pairs(&uncached));
+    }
+
+    #[test]
+    fn epsilon_rounds_exact_matches_and_ties() {
+        // The largest `f32` below 1.0, as left by the rounding errors of an otherwise exact match.
+        let similarity: f32 = 1.0 - f32::EPSILON / 2.0;
+        assert_eq!(similarity, 0.99999994);
+        assert!(Grapher::new(0.5, false).is_exact_match(similarity));
+        assert!(!Grapher::new(0.5, false).with_epsilon(0.0).unwrap().is_exact_match(similarity));
+
+        let reference = keyed_graph("lib.f", 0x1000, &[&["55", "4889e5"], &["e8", "c3"]]);
+        let samples: Vec<ControlFlowGraph> = vec![
+            keyed_graph("main.first", 0x2000, &[&["55", "4889e5"], &["e8", "cc", "cc"]]),
+            keyed_graph("main.tie", 0x3000, &[&["55", "4889e5"], &["e8", "cc", "cc"]]),
+            keyed_graph("main.better", 0x4000, &[&["55", "4889e5"], &["e8", "c3", "90", "90"]]),
+        ];
+        let grapher: Grapher = Grapher::new(0.1, false);
+        let first: f32 = grapher.graph_similarity(&reference, &samples[0]).unwrap();
+        let better: f32 = grapher.graph_similarity(&reference, &samples[2]).unwrap();
+        // Epsilons up to 1.5 times the gap leave the better similarity below the exact match tolerance.
+        assert!(first < better && (better - first) * 1.5 < 1.0 - better);
+        let best = |epsilon: f32| -> u64 {
+            let grapher: Grapher = grapher.clone().with_epsilon(epsilon).unwrap();
+            grapher.compare_against_graphs(&reference, &samples, false).unwrap().malware_offset()
+        };
+
+        // Ties keep the first match, which is only replaced by a similarity above it by more than epsilon.
+        assert_eq!(best(0.0), 0x4000);
+        assert_eq!(best((better - first) * 0.5), 0x4000);
+        assert_eq!(best((better - first) * 1.5), 0x2000);
+        assert_eq!(grapher.compare_against_graphs(&reference, &samples[..2], false).unwrap().malware_offset(), 0x2000);
+    }
+
+    #[test]
+    fn graph_norm_penalizes_subset_matches() {
+        // The reference is the entry of a larger sample function, which adds two blocks to it.
+        let reference = keyed_graph("lib.f", 0x1000, &[&["55", "4889e5"], &["e8", "c3"]]);
+        let sample = keyed_graph("main.f", 0x2000, &[&["55", "4889e5"], &["e8", "c3"], &["31c0"], &["90", "c3"]]);
+        let similarity = |graph_norm: GraphNorm| -> f32 {
+            Grapher::new(0.0, false).with_graph_norm(graph_norm).graph_similarity(&reference, &sample).unwrap()
+        };
+
+        // The pooled block similarities are normalized by 2, 4 and 3 blocks respectively.
+        let min: f32 = similarity(GraphNorm::Min);
+        assert!(min > 0.8);
+        assert_eq!(similarity(GraphNorm::Max), min / 2.0);
+        assert!((similarity(GraphNorm::Mean) - min * 2.0 / 3.0).abs() < 1e-6);
+        assert!(similarity(GraphNorm::Max) < similarity(GraphNorm::Mean));
+    }
+
+    #[test]
+    fn instruction_count_weighting_favors_large_blocks() {
+        // A large block shared by both functions, and a single instruction block that differs.
+        let large: &[&str] = &["55", "4889e5", "4883ec10", "897dfc", "8b45fc", "01c0", "8945f8", "8b45f8"];
+        let reference = keyed_graph("lib.f", 0x1000, &[large, &["c3"]]);
+        let sample = keyed_graph("main.f", 0x2000, &[large, &["cc"]]);
+        let similarity = |block_weighting: BlockWeighting| -> f32 {
+            Grapher::new(0.0, false)
+                .with_block_weighting(block_weighting)
+                .graph_similarity(&reference, &sample)
+                .unwrap()
+        };
+
+        let uniform: f32 = similarity(BlockWeighting::Uniform);
+        let weighted: f32 = similarity(BlockWeighting::InstructionCount);
+        assert!(uniform < weighted && weighted < 1.0, "uniform={uniform} weighted={weighted}");
+    }
+
+    #[test]
+    fn pooling_reads_the_best_block_similarities() {
+        let reference = keyed_graph("lib.f", 0x1000, &[&["55", "4889e5"], &["e8", "c3"], &["31c0", "90"]]);
+        let sample = keyed_graph("main.f", 0x2000, &[&["55", "4889e5"], &["e8", "cc"], &["90"], &["c3"]]);
+        let similarity = |pooling: Pooling| -> f32 {
+            Grapher::new(0.0, false).with_pooling(pooling).graph_similarity(&reference, &sample).unwrap()
+        };
+
+        // The best similarity of each reference block, in descending order.
+        let grapher: Grapher = Grapher::new(0.0, false);
+        let mut best: Vec<f32> = grapher
+            .block_similarity_matrix(&reference, &sample)
+            .iter()
+            .map(|row| row.iter().copied().fold(0.0, f32::max))
+            .collect();
+        best.sort_by(|x, y| y.total_cmp(x));
+        assert!(best[0] > best[1] && best[1] > best[2]);
+        let mean = |similarities: &[f32]| similarities.iter().sum::<f32>() / similarities.len() as f32;
+
+        assert_eq!(similarity(Pooling::Max), best[0]);
+        assert!((similarity(top_k(2)) - mean(&best[..2])).abs() < 1e-6);
+        assert!((similarity(Pooling::Mean) - mean(&best)).abs() < 1e-6);
+        assert!((similarity(top_k(9)) - similarity(Pooling::Mean)).abs() < 1e-6);
+        assert!(similarity(Pooling::Max) > similarity(top_k(2)));
+        assert!(similarity(top_k(2)) > similarity(Pooling::Mean));
+    }
+
+    #[test]
+    fn size_ratio_gate_keeps_matches_of_similar_sizes() {
+        let pool: [&[&str]; 6] =
+            [&["55", "4889e5"], &["e8", "c3"], &["31c0"], &["90", "c3"], &["cc"], &["4883ec08", "e8"]];
+        // Functions of 1 to 6 blocks sharing their leading blocks, so that sizes alone set them apart.
+        let graphs = |name: &str, base: u64, rotation: usize| -> Vec<ControlFlowGraph> {
+            (1..=pool.len())
+                .map(|size| {
+                    let blocks: Vec<&[&str]> = (0..size).map(|index| pool[(index + rotation) % pool.len()]).collect();
+                    keyed_graph(&format!("{name}.f{size}"), base + size as u64 * 0x100, &blocks)
+                })
+                .collect()
+        };
+        let sample: Disassembly = disassembly("sample", graphs("main", 0x1000, 1));
+        let reference: Disassembly = disassembly("reference", graphs("lib", 0x10000, 0));
+        let sizes: HashMap<u64, usize> = sample
+            .graphs
+            .iter()
+            .chain(&reference.graphs)
+            .map(|graph| (graph.offset, graph.blocks.len()))
+            .collect();
+        let ratio: f32 = 0.5;
+
+        let grapher: Grapher = Grapher::new(0.1, false);
+        let gated: Grapher = grapher.clone().with_size_ratio_gate(Some(ratio)).unwrap();
+        let ungated_pairs = report_pairs(&grapher.compare(&sample, vec![&reference]).unwrap());
+        let gated_pairs = report_pairs(&gated.compare(&sample, vec![&reference]).unwrap());
+        let passes = |(_, sample, reference, _): &&(String, u64, u64, u32)| {
+            let (l, r) = (sizes[sample], sizes[reference]);
+            l.min(r) as f32 >= ratio * l.max(r) as f32
+        };
+
+        // Matches whose sizes pass the gate are unchanged, the others are the ones being pruned.
+        let kept: Vec<&(String, u64, u64, u32)> = ungated_pairs.iter().filter(passes).collect();
+        assert!(!kept.is_empty() && kept.len() < ungated_pairs.len());
+        assert!(kept.iter().all(|pair| gated_pairs.contains(pair)));
+        assert!(gated_pairs.iter().all(|pair| passes(&pair)));
+    }
+
+    #[test]
+    fn builders_reject_invalid_options() {
+        let grapher: Grapher = Grapher::new(0.5, false);
+        let is_invalid = |result: Result<Grapher, Error>, expected: &str| -> bool {
+            matches!(result, Err(Error::InvalidOption { option, .. }) if option == expected)
+        };
+
+        for ratio in [0.0, -0.5, 1.5, f32::NAN] {
+            assert!(is_invalid(grapher.clone().with_size_ratio_gate(Some(ratio)), "size_ratio_gate"));
+        }
+        for bound in [-0.5, 1.5, f32::NAN] {
+            assert!(is_invalid(grapher.clone().with_bloom_prefilter(Some(bound)), "bloom_prefilter"));
+        }
+        for epsilon in [-1e-6, 1.0, f32::NAN] {
+            assert!(is_invalid(grapher.clone().with_epsilon(epsilon), "epsilon"));
         }
+        for report_floor in [-0.5, 1.5, f32::NAN] {
+            assert!(is_invalid(grapher.clone().with_report_floor(report_floor), "report_floor"));
+        }
+        for confidence_scale in [0.0, -1.0, f32::NAN] {
+            assert!(is_invalid(grapher.clone().with_confidence_scale(confidence_scale), "confidence_scale"));
+        }
+
+        // The bounds of the valid ranges, and the disabled options.
+        assert!(grapher.clone().with_size_ratio_gate(Some(1.0)).is_ok());
+        assert!(grapher.clone().with_size_ratio_gate(None).is_ok());
+        assert!(grapher.clone().with_bloom_prefilter(Some(0.0)).is_ok());
+        assert!(grapher.clone().with_bloom_prefilter(None).is_ok());
+        assert!(grapher.clone().with_epsilon(0.0).is_ok());
+        assert!(grapher.clone().with_report_floor(1.0).is_ok());
+        assert!(grapher.with_confidence_scale(0.5).is_ok());
+    }
+
+    // A function calling `target` between its prologue and return, the call bytes being relocated.
+    fn calling_graph(name: &str, offset: u64, target: &str) -> ControlFlowGraph {
+        let instructions: Vec<Instruction> = vec![
+            instruction(offset, "55", "push", "rbp"),
+            instruction(offset + 1, "e800000000", "call", "0"),
+            instruction(offset + 6, "c3", "ret", ""),
+        ];
+        let call_targets: Vec<Option<String>> = vec![None, Some(target.to_string()), None];
+        let block = BasicBlock::with_call_targets(offset, &instructions, call_targets, NormalizationMode::default());
+        chain_graph(name, offset, vec![block])
+    }
+
+    #[test]
+    fn call_targets_tell_apart_identical_calls() {
+        let reference = calling_graph("lib.f", 0x1000, "os.Open");
+        let sample = calling_graph("main.f", 0x2000, "os.Remove");
+        let similarity = |use_call_targets: bool| -> f32 {
+            Grapher::new(0.0, false).with_call_targets(use_call_targets).graph_similarity(&reference, &sample).unwrap()
+        };
+
+        assert_eq!(similarity(false), 1.0);
+        assert!(similarity(true) < 1.0);
+        let same_target = calling_graph("main.g", 0x3000, "os.Open");
+        let grapher: Grapher = Grapher::new(0.0, false).with_call_targets(true);
+        assert_eq!(grapher.graph_similarity(&reference, &same_target).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn fuzzy_bytes_keep_call_targets_exact() {
+        let grapher: Grapher = Grapher::new(0.0, false).with_call_targets(true).with_fuzzy_bytes(true);
+        // Targets whose keys differ by a single hex pair: `e800000000:os.f1` and `e800000000:os.f2`.
+        let reference = calling_graph("lib.f", 0x1000, "os.f1");
+        let sample = calling_graph("main.f", 0x2000, "os.f2");
+        assert!(grapher.graph_similarity(&reference, &sample).unwrap() < 1.0);
+
+        // The same target called with a displacement one byte apart.
+        let instructions: Vec<Instruction> = vec![
+            instruction(0x3000, "55", "push", "rbp"),
+            instruction(0x3001, "e801000000", "call", "1"),
+            instruction(0x3006, "c3", "ret", ""),
+        ];
+        let call_targets: Vec<Option<String>> = vec![None, Some("os.f1".to_string()), None];
+        let block = BasicBlock::with_call_targets(0x3000, &instructions, call_targets, NormalizationMode::default());
+        let displaced = chain_graph("main.g", 0x3000, vec![block]);
+        assert_eq!(grapher.graph_similarity(&reference, &displaced).unwrap(), 1.0);
+        assert!(grapher.with_fuzzy_bytes(false).graph_similarity(&reference, &displaced).unwrap() < 1.0);
+    }
+
+    #[test]
+    fn prefix_blocks_compare_the_entry_region() {
+        let entry: [&[&str]; 2] = [&["55", "4889e5"], &["4883ec10", "e8"]];
+        let reference = keyed_graph("lib.f", 0x1000, &[entry[0], entry[1], &["31c0"], &["c3"]]);
+        // The same entry diverging afterwards, and a modified entry followed by the same body.
+        let diverging = keyed_graph("main.f", 0x2000, &[entry[0], entry[1], &["90", "cc"], &["0f0b"], &["cc"]]);
+        let modified = keyed_graph("main.g", 0x3000, &[&["cc"], &["6548", "90"], &["31c0"], &["c3"]]);
+        let similarity = |prefix_blocks: Option<usize>, sample: &ControlFlowGraph| -> f32 {
+            Grapher::new(0.0, false)
+                .with_prefix_blocks(prefix_blocks.and_then(NonZeroUsize::new))
+                .graph_similarity(&reference, sample)
+                .unwrap()
+        };
+
+        assert_eq!(similarity(Some(2), &diverging), 1.0);
+        assert!(similarity(None, &diverging) < 1.0);
+        assert!(similarity(Some(2), &modified) < similarity(None, &modified));
+        // A prefix longer than the graphs compares them whole.
+        assert_eq!(similarity(Some(64), &diverging), similarity(None, &diverging));
+    }
+
+    #[test]
+    fn min_block_instructions_keeps_short_blocks_compared() {
+        // Graphs only differing in their short exit block, which their hashes leave out.
+        let reference = keyed_graph("lib.f", 0x1000, &[&["55", "4889e5", "31c0"], &["c3"]]);
+        let sample = keyed_graph("main.f", 0x2000, &[&["55", "4889e5", "31c0"], &["0f0b"]]);
+        let grapher: Grapher = Grapher::new(0.1, false);
+        let short: Grapher = grapher.clone().with_min_block_instructions(2);
+
+        let similarity: f32 = short.graph_similarity(&reference, &sample).unwrap();
+        assert!(similarity < 1.0);
+        assert_eq!(similarity, grapher.graph_similarity(&reference, &sample).unwrap());
+
+        let report: CompareReport = short
+            .compare(&disassembly("sample", vec![sample]), vec![&disassembly("reference", vec![reference])])
+            .unwrap();
+        let method: &MethodMatch = &report.matches()[0].matches()[0];
+        assert!(method.similarity() < 1.0);
+        assert!(!method.exact());
     }
 }
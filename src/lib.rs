@@ -7,32 +7,68 @@ use pyo3::{
 };
 
 pub use self::cli::Cli;
-pub use self::compare_report::CompareReport;
-pub use self::control_flow_graph::{BasicBlock, ControlFlowGraph};
-pub use self::disassembly::Disassembly;
+pub use self::compare_report::{BinaryMatchReader, CompareConfig, CompareReport};
+pub use self::control_flow_graph::{BasicBlock, ControlFlowGraph, NormalizationMode};
+pub use self::disassembly::{DisasmOptions, Disassembly};
 pub use self::error::Error;
-pub use self::grapher::Grapher;
-pub use self::r#match::{Binary as BinaryMatch, Method as MethodMatch};
+pub use self::grapher::{
+    Alignment, BlockWeighting, GraphNorm, Grapher, LenientGraphs, Pooling, ProgressMode, ProgressTheme,
+};
+pub use self::logging::Logging;
+pub use self::method_diff::{BlockAlignment, BlockDiff, MethodDiff};
+pub use self::r#match::{Binary as BinaryMatch, Method as MethodMatch, Package as PackageMatch};
+pub use self::report_diff::ReportDiff;
+pub use self::signature_db::SignatureDb;
 
+mod checkpoint;
 mod cli;
 mod compare_report;
 mod control_flow_graph;
 mod disassembly;
 mod error;
 mod grapher;
+mod logging;
+mod method_diff;
 mod r#match;
+mod report_diff;
+mod signature_db;
+#[cfg(test)]
+mod test_utils;
 
 // Python entrypoint
 #[pymodule]
 fn gographer(module: &Bound<'_, PyModule>) -> PyResult<()> {
     module.add_class::<MethodMatch>()?;
     module.add_class::<BinaryMatch>()?;
+    module.add_class::<PackageMatch>()?;
+    module.add_class::<BasicBlock>()?;
     module.add_class::<ControlFlowGraph>()?;
     module.add_class::<Disassembly>()?;
+    module.add_class::<CompareConfig>()?;
     module.add_class::<CompareReport>()?;
+    module.add_class::<BlockDiff>()?;
+    module.add_class::<MethodDiff>()?;
+    module.add_class::<BlockAlignment>()?;
+    module.add_class::<ReportDiff>()?;
+    module.add_class::<SignatureDb>()?;
     module.add_class::<Grapher>()?;
     module.add_class::<Cli>()?;
+    module.add_class::<Logging>()?;
+    module.add_class::<self::error::PyGoGrapherError>()?;
     module.add_class::<self::error::PyUnsupportedBinaryFormat>()?;
+    module.add_class::<self::error::PyDisassemblyPanicked>()?;
+    module.add_class::<self::error::PyDisassemblyFailed>()?;
+    module.add_class::<self::error::PySampleNotFound>()?;
+    module.add_class::<self::error::PySampleNameMismatch>()?;
+    module.add_class::<self::error::PyFileAccessError>()?;
+    module.add_class::<self::error::PyInvalidSignatureDb>()?;
+    module.add_class::<self::error::PyInvalidGlob>()?;
+    module.add_class::<self::error::PyInvalidRegex>()?;
+    module.add_class::<self::error::PyUnsupportedReportVersion>()?;
+    module.add_class::<self::error::PyFunctionNotFound>()?;
+    module.add_class::<self::error::PyInvalidSimilarity>()?;
+    module.add_class::<self::error::PyInvalidReport>()?;
+    module.add_class::<self::error::PyInvalidOption>()?;
 
     Ok(())
 }
@@ -0,0 +1,71 @@
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use pyo3::{exceptions::PyValueError, pyclass, pymethods, types::PyAnyMethods, PyResult, Python};
+
+/// Forwards the diagnostics of GoGrapher to Python's `logging` module.
+///
+/// The `tracing` events are emitted as `log` records when no `tracing` subscriber is installed,
+/// each record is then logged to the Python logger named after its module (eg: "gographer.grapher").
+struct PythonLogger;
+
+static LOGGER: PythonLogger = PythonLogger;
+
+impl Log for PythonLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+
+        // Python's logging levels, TRACE being below DEBUG.
+        let level: u8 = match record.level() {
+            Level::Error => 40,
+            Level::Warn => 30,
+            Level::Info => 20,
+            Level::Debug => 10,
+            Level::Trace => 5,
+        };
+        let name: String = record.target().replace("::", ".");
+        let message: String = record.args().to_string();
+
+        Python::with_gil(|py| {
+            let _ = py
+                .import("logging")
+                .and_then(|logging| logging.call_method1("getLogger", (name,)))
+                .and_then(|logger| logger.call_method1("log", (level, message)));
+        });
+    }
+
+    fn flush(&self) {}
+}
+
+/// Routes the diagnostics of GoGrapher into Python's `logging` module.
+#[pyclass]
+pub struct Logging;
+
+#[pymethods]
+impl Logging {
+    /// Forward the diagnostics of GoGrapher at or above `level` to Python's `logging` module.
+    ///
+    /// May be called again to change the level.
+    #[staticmethod]
+    #[pyo3(name = "init", signature = (level="debug"))]
+    fn init_py(level: &str) -> PyResult<()> {
+        let level: LevelFilter = match level {
+            "off" => LevelFilter::Off,
+            "error" => LevelFilter::Error,
+            "warning" => LevelFilter::Warn,
+            "info" => LevelFilter::Info,
+            "debug" => LevelFilter::Debug,
+            "trace" => LevelFilter::Trace,
+            other => return Err(PyValueError::new_err(format!("Unknown logging level {other:?}."))),
+        };
+
+        // Only the first call installs the logger, later calls update the level.
+        let _ = log::set_logger(&LOGGER);
+        log::set_max_level(level);
+        Ok(())
+    }
+}
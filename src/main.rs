@@ -1,8 +1,10 @@
 extern crate gographer;
 
+use std::process::ExitCode;
+
 use gographer::Cli;
 
 /// Standalone entrypoint, calls the command line interface.
-fn main() {
-    Cli::run_cli();
+fn main() -> ExitCode {
+    Cli::run_cli()
 }
@@ -1,4 +1,6 @@
-use pyo3::pyclass;
+use std::{collections::HashSet, time::Duration};
+
+use pyo3::{pyclass, pymethods};
 use serde::{Deserialize, Serialize};
 
 use crate::control_flow_graph::ControlFlowGraph;
@@ -17,24 +19,52 @@ pub struct Method {
     clean_offset: u64,
     #[pyo3(get)]
     pub(crate) similarity: f32,
+    #[pyo3(get)]
+    #[serde(default)]
+    confidence: f32,
+    #[pyo3(get)]
+    #[serde(default)]
+    exact: bool,
+    #[pyo3(get)]
+    #[serde(default)]
+    reference: String,
+    #[pyo3(get)]
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    block_alignment: Option<Vec<(usize, usize, f32)>>,
 }
 
 impl Method {
     /// Create a new MethodMatch instance.
+    ///
+    /// The `confidence_scale` is the `k` of the saturating confidence function, see `confidence`.
     pub fn new(
         malware_graph: &ControlFlowGraph,
         clean_graph: &ControlFlowGraph,
         similarity: f32,
+        confidence_scale: f32,
     ) -> Self {
+        let blocks: usize = std::cmp::min(malware_graph.blocks.len(), clean_graph.blocks.len());
+        let confidence: f32 = similarity * (1.0 - (-(blocks as f32) / confidence_scale).exp());
+
         Self {
             old_name: malware_graph.name.to_string(),
             resolved_name: clean_graph.name.to_string(),
             malware_offset: malware_graph.offset,
             clean_offset: clean_graph.offset,
             similarity,
+            confidence,
+            exact: malware_graph.is_identical(clean_graph),
+            reference: String::new(),
+            block_alignment: None,
         }
     }
 
+    /// Attach the block alignment of the match, see `block_alignment`.
+    pub(crate) fn with_block_alignment(mut self, block_alignment: Vec<(usize, usize, f32)>) -> Self {
+        self.block_alignment = Some(block_alignment);
+        self
+    }
+
     /// Name of the sample method.
     #[inline]
     pub fn old_name(&self) -> &String {
@@ -64,6 +94,51 @@ impl Method {
     pub fn similarity(&self) -> f32 {
         self.similarity
     }
+
+    /// Similarity scaled down for small methods, `similarity * (1 - exp(-blocks / k))`.
+    ///
+    /// Where `blocks` is the block count of the smaller of the two methods and `k` the grapher's
+    /// confidence scale. Small methods match by chance far more often than large ones, the
+    /// confidence saturates towards the similarity as the methods grow (~63% of it at `k` blocks,
+    /// ~95% at `3k` blocks).
+    #[inline]
+    pub fn confidence(&self) -> f32 {
+        self.confidence
+    }
+
+    /// Name of the reference binary the clean method belongs to, the `dest` of the enclosing
+    /// binary match.
+    ///
+    /// Keeps the provenance of flattened matches, the same symbol often existing in several
+    /// references.
+    #[inline]
+    pub fn reference(&self) -> &String {
+        &self.reference
+    }
+
+    /// Whether the hashes of both methods matched, their similarity then being exactly 1.0 rather
+    /// than computed by the block matcher. `false` in older reports.
+    #[inline]
+    pub fn exact(&self) -> bool {
+        self.exact
+    }
+
+    /// The (sample_block_index, reference_block_index, similarity) pairing of each sample block
+    /// with its most similar reference block, if recorded by the grapher.
+    #[inline]
+    pub fn block_alignment(&self) -> Option<&Vec<(usize, usize, f32)>> {
+        self.block_alignment.as_ref()
+    }
+}
+
+#[pymethods]
+impl Method {
+    fn __repr__(&self) -> String {
+        format!(
+            "MethodMatch(old='{}', resolved='{}', sim={:.2}, conf={:.2})",
+            self.old_name, self.resolved_name, self.similarity, self.confidence
+        )
+    }
 }
 
 /// Data Model of the similarity between the Control Flow Gaphs (CFG) of two binaries.
@@ -77,18 +152,71 @@ pub struct Binary {
     #[pyo3(get)]
     dest: String,
     #[pyo3(get)]
+    #[serde(default)]
+    containment: f32,
+    #[pyo3(get)]
+    #[serde(default)]
+    sample_function_count: usize,
+    #[pyo3(get)]
+    #[serde(default)]
+    reference_function_count: usize,
+    #[pyo3(get)]
+    #[serde(default)]
+    compute_time_secs: f64,
+    #[pyo3(get)]
     matches: Vec<Method>,
 }
 
 impl Binary {
     /// Create a new BinaryMatch instance.
-    pub fn new(source: &str, dest: &str, matches: &[Method]) -> Self {
+    ///
+    /// The `sample_function_count` and `reference_function_count` are the number of functions of
+    /// the `source` and `dest` binaries that were compared, each `dest` function matching at most
+    /// once.
+    pub fn new(
+        source: &str,
+        dest: &str,
+        matches: &[Method],
+        sample_function_count: usize,
+        reference_function_count: usize,
+    ) -> Self {
+        let containment: f32 = if reference_function_count == 0 {
+            0.0
+        } else {
+            matches.len() as f32 / reference_function_count as f32
+        };
+
         Self {
             similarity: matches.iter().map(|m| m.similarity).sum::<f32>() / matches.len() as f32,
+            containment,
+            sample_function_count,
+            reference_function_count,
+            compute_time_secs: 0.0,
             source: source.to_string(),
             dest: dest.to_string(),
             matches: matches.to_vec(),
         }
+        .with_method_references()
+    }
+
+    /// Set the reference of each method match to the `dest` binary, see `Method::reference`.
+    pub(crate) fn with_method_references(mut self) -> Self {
+        for method in &mut self.matches {
+            method.reference.clone_from(&self.dest);
+        }
+        self
+    }
+
+    /// Record the time spent comparing the binaries, see `compute_time_secs`.
+    pub(crate) fn with_compute_time(mut self, compute_time: Duration) -> Self {
+        self.compute_time_secs = compute_time.as_secs_f64();
+        self
+    }
+
+    /// Drop the method matches below `floor`, keeping the aggregates computed from all of them.
+    pub(crate) fn with_floor(mut self, floor: f32) -> Self {
+        self.matches.retain(|method| method.similarity >= floor);
+        self
     }
 
     /// Normalized similarity ratio between the two binaries.
@@ -97,6 +225,36 @@ impl Binary {
         self.similarity
     }
 
+    /// Fraction of the `dest` functions whose best `source` match reached the threshold.
+    ///
+    /// Unlike the similarity this metric is directional, it measures how much of the reference
+    /// is contained within the sample, regardless of how much else the sample holds. A sample
+    /// embedding a whole library scores close to 1.0 against it.
+    #[inline]
+    pub fn containment(&self) -> f32 {
+        self.containment
+    }
+
+    /// Number of functions of the `source` binary that were compared, `0` in older reports.
+    #[inline]
+    pub fn sample_function_count(&self) -> usize {
+        self.sample_function_count
+    }
+
+    /// Number of functions of the `dest` binary that were compared, `0` in older reports.
+    ///
+    /// Along with the number of matches, gives the recall of the reference's functions.
+    #[inline]
+    pub fn reference_function_count(&self) -> usize {
+        self.reference_function_count
+    }
+
+    /// Time spent comparing the binaries in seconds, `0` in older reports.
+    #[inline]
+    pub fn compute_time_secs(&self) -> f64 {
+        self.compute_time_secs
+    }
+
     /// The name of the source binary during testing.
     #[inline]
     pub fn source(&self) -> &String {
@@ -114,4 +272,154 @@ impl Binary {
     pub fn matches(&self) -> &Vec<Method> {
         &self.matches
     }
+
+    /// Returns the method match of the malware method at `offset`, if any.
+    ///
+    /// Matches are not ordered by offset, the lookup is a linear scan in `O(n)`.
+    pub fn method_by_malware_offset(&self, offset: u64) -> Option<&Method> {
+        self.matches.iter().find(|method| method.malware_offset == offset)
+    }
+
+    /// Returns the method match of the clean method at `offset`, if any.
+    ///
+    /// Matches are not ordered by offset, the lookup is a linear scan in `O(n)`.
+    pub fn method_by_clean_offset(&self, offset: u64) -> Option<&Method> {
+        self.matches.iter().find(|method| method.clean_offset == offset)
+    }
+
+    /// Returns the method match with the highest similarity, if any.
+    ///
+    /// Ties are broken by the lowest malware offset, so that the result does not depend on the
+    /// order in which the matches were found.
+    pub fn best_method(&self) -> Option<&Method> {
+        self.matches.iter().max_by(|lhs, rhs| {
+            lhs.similarity
+                .total_cmp(&rhs.similarity)
+                .then_with(|| rhs.malware_offset.cmp(&lhs.malware_offset))
+        })
+    }
+
+    /// Returns the method matches by decreasing similarity, NaN similarities last.
+    ///
+    /// Ties are broken by the lowest malware offset, as with `best_method`.
+    pub fn sorted_matches(&self) -> Vec<Method> {
+        let key = |method: &Method| -> f32 {
+            if method.similarity.is_nan() { f32::NEG_INFINITY } else { method.similarity }
+        };
+        let mut matches: Vec<Method> = self.matches.clone();
+        matches.sort_by(|lhs, rhs| {
+            key(rhs)
+                .total_cmp(&key(lhs))
+                .then_with(|| lhs.malware_offset.cmp(&rhs.malware_offset))
+        });
+        matches
+    }
+}
+
+#[pymethods]
+impl Binary {
+    fn __repr__(&self) -> String {
+        format!(
+            "BinaryMatch(source='{}', dest='{}', sim={:.2}, containment={:.2}, matches={})",
+            self.source,
+            self.dest,
+            self.similarity,
+            self.containment,
+            self.matches.len()
+        )
+    }
+
+    #[pyo3(name = "method_by_malware_offset")]
+    fn py_method_by_malware_offset(&self, offset: u64) -> Option<Method> {
+        self.method_by_malware_offset(offset).cloned()
+    }
+
+    #[pyo3(name = "method_by_clean_offset")]
+    fn py_method_by_clean_offset(&self, offset: u64) -> Option<Method> {
+        self.method_by_clean_offset(offset).cloned()
+    }
+
+    #[pyo3(name = "best_method")]
+    fn py_best_method(&self) -> Option<Method> {
+        self.best_method().cloned()
+    }
+
+    #[pyo3(name = "sorted_matches")]
+    fn py_sorted_matches(&self) -> Vec<Method> {
+        self.sorted_matches()
+    }
+}
+
+/// Data Model of the matches of a Go package, grouping the method matches by resolved package.
+#[pyclass(name = "PackageMatch")]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Package {
+    #[pyo3(get)]
+    package: String,
+    #[pyo3(get)]
+    match_count: usize,
+    #[pyo3(get)]
+    function_count: usize,
+    #[pyo3(get)]
+    mean_similarity: f32,
+}
+
+impl Package {
+    /// Create a new PackageMatch instance from the method matches resolved to the `package`.
+    pub fn new(package: &str, matches: &[&Method]) -> Self {
+        let functions: HashSet<&String> = matches.iter().map(|method| &method.resolved_name).collect();
+
+        Self {
+            package: package.to_string(),
+            match_count: matches.len(),
+            function_count: functions.len(),
+            mean_similarity: matches.iter().map(|m| m.similarity).sum::<f32>() / matches.len() as f32,
+        }
+    }
+
+    /// Returns the package path of a Go symbol name, `None` if the name has no package.
+    ///
+    /// The path ends at the first `.` following its last `/`, so that `crypto/tls.(*Conn).Read`
+    /// belongs to `crypto/tls`. Slashes within the receiver or type parameters are ignored. Paths
+    /// whose last element contains a dot, such as `gopkg.in/yaml.v2`, are truncated at that dot.
+    pub fn package_path(symbol: &str) -> Option<&str> {
+        let prefix: &str = symbol.split(['(', '[']).next().unwrap_or(symbol);
+        let start: usize = prefix.rfind('/').map_or(0, |slash| slash + 1);
+        let end: usize = start + symbol[start..].find('.')?;
+        (end > 0).then(|| &symbol[..end])
+    }
+
+    /// The package path of the resolved methods.
+    #[inline]
+    pub fn package(&self) -> &String {
+        &self.package
+    }
+
+    /// Number of method matches resolved to the package.
+    #[inline]
+    pub fn match_count(&self) -> usize {
+        self.match_count
+    }
+
+    /// Number of distinct package functions among the matches.
+    #[inline]
+    pub fn function_count(&self) -> usize {
+        self.function_count
+    }
+
+    /// Mean similarity of the method matches resolved to the package.
+    #[inline]
+    pub fn mean_similarity(&self) -> f32 {
+        self.mean_similarity
+    }
+}
+
+#[pymethods]
+impl Package {
+    fn __repr__(&self) -> String {
+        format!(
+            "PackageMatch(package='{}', matches={}, functions={}, sim={:.2})",
+            self.package, self.match_count, self.function_count, self.mean_similarity
+        )
+    }
 }
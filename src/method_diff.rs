@@ -0,0 +1,173 @@
+use pyo3::pyclass;
+use serde::{Deserialize, Serialize};
+
+/// Data Model of the instruction differences between two aligned basic blocks.
+#[pyclass]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BlockDiff {
+    #[pyo3(get)]
+    source_offset: Option<u64>,
+    #[pyo3(get)]
+    target_offset: Option<u64>,
+    #[pyo3(get)]
+    similarity: f32,
+    #[pyo3(get)]
+    removed: Vec<String>,
+    #[pyo3(get)]
+    added: Vec<String>,
+}
+
+impl BlockDiff {
+    /// Create a new BlockDiff instance.
+    pub fn new(
+        source_offset: Option<u64>,
+        target_offset: Option<u64>,
+        similarity: f32,
+        removed: Vec<String>,
+        added: Vec<String>,
+    ) -> Self {
+        Self {
+            source_offset,
+            target_offset,
+            similarity,
+            removed,
+            added,
+        }
+    }
+
+    /// Offset of the source block, `None` if the block only exists in the target method.
+    #[inline]
+    pub fn source_offset(&self) -> Option<u64> {
+        self.source_offset
+    }
+
+    /// Offset of the target block, `None` if the block only exists in the source method.
+    #[inline]
+    pub fn target_offset(&self) -> Option<u64> {
+        self.target_offset
+    }
+
+    /// Normalized similarity ratio between the two blocks.
+    #[inline]
+    pub fn similarity(&self) -> f32 {
+        self.similarity
+    }
+
+    /// Instructions of the source block missing from the target block.
+    #[inline]
+    pub fn removed(&self) -> &Vec<String> {
+        &self.removed
+    }
+
+    /// Instructions of the target block missing from the source block.
+    #[inline]
+    pub fn added(&self) -> &Vec<String> {
+        &self.added
+    }
+}
+
+/// Data Model of the instruction differences between two methods.
+#[pyclass]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct MethodDiff {
+    #[pyo3(get)]
+    source_name: String,
+    #[pyo3(get)]
+    target_name: String,
+    #[pyo3(get)]
+    blocks: Vec<BlockDiff>,
+}
+
+impl MethodDiff {
+    /// Create a new MethodDiff instance.
+    pub fn new(source_name: &str, target_name: &str, blocks: Vec<BlockDiff>) -> Self {
+        Self {
+            source_name: source_name.to_string(),
+            target_name: target_name.to_string(),
+            blocks,
+        }
+    }
+
+    /// Name of the source method.
+    #[inline]
+    pub fn source_name(&self) -> &String {
+        &self.source_name
+    }
+
+    /// Name of the target method.
+    #[inline]
+    pub fn target_name(&self) -> &String {
+        &self.target_name
+    }
+
+    /// The differences of each aligned block pair.
+    #[inline]
+    pub fn blocks(&self) -> &Vec<BlockDiff> {
+        &self.blocks
+    }
+}
+
+/// Data Model of the one to one alignment of the basic blocks of a sample and a reference method.
+#[pyclass]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct BlockAlignment {
+    #[pyo3(get)]
+    sample_name: String,
+    #[pyo3(get)]
+    reference_name: String,
+    #[pyo3(get)]
+    matched: Vec<(u64, u64, f32)>,
+    #[pyo3(get)]
+    sample_only: Vec<u64>,
+    #[pyo3(get)]
+    reference_only: Vec<u64>,
+}
+
+impl BlockAlignment {
+    /// Create a new BlockAlignment instance.
+    pub fn new(
+        sample_name: &str,
+        reference_name: &str,
+        matched: Vec<(u64, u64, f32)>,
+        sample_only: Vec<u64>,
+        reference_only: Vec<u64>,
+    ) -> Self {
+        Self {
+            sample_name: sample_name.to_string(),
+            reference_name: reference_name.to_string(),
+            matched,
+            sample_only,
+            reference_only,
+        }
+    }
+
+    /// Name of the sample method.
+    #[inline]
+    pub fn sample_name(&self) -> &String {
+        &self.sample_name
+    }
+
+    /// Name of the reference method.
+    #[inline]
+    pub fn reference_name(&self) -> &String {
+        &self.reference_name
+    }
+
+    /// The (sample_offset, reference_offset, similarity) of each aligned block pair, by sample offset.
+    #[inline]
+    pub fn matched(&self) -> &Vec<(u64, u64, f32)> {
+        &self.matched
+    }
+
+    /// Offsets of the sample blocks without a counterpart in the reference method.
+    #[inline]
+    pub fn sample_only(&self) -> &Vec<u64> {
+        &self.sample_only
+    }
+
+    /// Offsets of the reference blocks without a counterpart in the sample method.
+    #[inline]
+    pub fn reference_only(&self) -> &Vec<u64> {
+        &self.reference_only
+    }
+}
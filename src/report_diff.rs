@@ -0,0 +1,67 @@
+use pyo3::{pyclass, pymethods};
+use serde::{Deserialize, Serialize};
+
+/// Data Model of the differences between the binary matches of two compare reports.
+///
+/// Binary matches are identified by the name of their reference.
+#[pyclass]
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ReportDiff {
+    #[pyo3(get)]
+    appeared: Vec<String>,
+    #[pyo3(get)]
+    disappeared: Vec<String>,
+    #[pyo3(get)]
+    changed: Vec<(String, f32, f32)>,
+}
+
+impl ReportDiff {
+    /// Create a new ReportDiff instance.
+    pub fn new(appeared: Vec<String>, disappeared: Vec<String>, changed: Vec<(String, f32, f32)>) -> Self {
+        Self {
+            appeared,
+            disappeared,
+            changed,
+        }
+    }
+
+    /// References only matched in the new report.
+    #[inline]
+    pub fn appeared(&self) -> &Vec<String> {
+        &self.appeared
+    }
+
+    /// References only matched in the old report.
+    #[inline]
+    pub fn disappeared(&self) -> &Vec<String> {
+        &self.disappeared
+    }
+
+    /// The (reference, old_similarity, new_similarity) of references whose similarity changed.
+    #[inline]
+    pub fn changed(&self) -> &Vec<(String, f32, f32)> {
+        &self.changed
+    }
+
+    /// Whether both reports hold the same binary matches.
+    pub fn is_empty(&self) -> bool {
+        self.appeared.is_empty() && self.disappeared.is_empty() && self.changed.is_empty()
+    }
+}
+
+#[pymethods]
+impl ReportDiff {
+    fn __repr__(&self) -> String {
+        format!(
+            "ReportDiff(appeared={}, disappeared={}, changed={})",
+            self.appeared.len(),
+            self.disappeared.len(),
+            self.changed.len()
+        )
+    }
+
+    #[pyo3(name = "is_empty")]
+    fn py_is_empty(&self) -> bool {
+        self.is_empty()
+    }
+}
@@ -0,0 +1,297 @@
+use std::{
+    borrow::Borrow,
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+};
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use pyo3::{pyclass, pymethods, PyRef, PyResult};
+use serde::{Deserialize, Serialize};
+use smda::{function::Instruction, FileArchitecture};
+
+use crate::control_flow_graph::{BasicBlock, ControlFlowGraph, NormalizationMode};
+use crate::disassembly::Disassembly;
+use crate::error::Error;
+
+/// Version of the signature database file format.
+const SIGNATURE_DB_VERSION: u32 = 1;
+
+// Serialized form of an instruction: offset, hex encoded bytes, mnemonic and operands.
+type StoredInstruction = (u64, String, String, Option<String>);
+
+// Serialized form of a basic block, its keys and hash are recomputed when loaded.
+#[derive(Serialize, Deserialize)]
+struct StoredBlock {
+    offset: u64,
+    in_refs: Vec<usize>,
+    out_refs: Vec<usize>,
+    instructions: Vec<StoredInstruction>,
+    #[serde(default)]
+    call_targets: Vec<Option<String>>,
+}
+
+// Serialized form of a Control Flow Graph (CFG).
+#[derive(Serialize, Deserialize)]
+struct StoredGraph {
+    name: String,
+    offset: u64,
+    is_exported: bool,
+    blocks: Vec<StoredBlock>,
+}
+
+// Serialized form of a disassembly.
+#[derive(Serialize, Deserialize)]
+struct StoredDisassembly {
+    name: String,
+    path: PathBuf,
+    format: String,
+    arch: String,
+    bits: u8,
+    normalization: NormalizationMode,
+    graphs: Vec<StoredGraph>,
+}
+
+// Serialized form of the signature database.
+#[derive(Serialize, Deserialize)]
+struct StoredSignatureDb {
+    version: u32,
+    disassemblies: Vec<StoredDisassembly>,
+}
+
+impl From<&Disassembly> for StoredDisassembly {
+    fn from(disassembly: &Disassembly) -> Self {
+        Self {
+            name: disassembly.name.clone(),
+            path: disassembly.path.clone(),
+            format: disassembly.format.clone(),
+            arch: disassembly.arch.clone(),
+            bits: disassembly.bits,
+            normalization: disassembly.normalization,
+            graphs: disassembly
+                .graphs
+                .iter()
+                .map(|graph| StoredGraph {
+                    name: graph.name.clone(),
+                    offset: graph.offset,
+                    is_exported: graph.is_exported,
+                    blocks: graph
+                        .blocks
+                        .iter()
+                        .map(|block| StoredBlock {
+                            offset: block.offset,
+                            in_refs: block.in_refs.clone(),
+                            out_refs: block.out_refs.clone(),
+                            instructions: block
+                                .instructions
+                                .iter()
+                                .map(|ins| (ins.offset, ins.bytes.clone(), ins.mnemonic.clone(), ins.operands.clone()))
+                                .collect(),
+                            call_targets: block.call_targets.clone(),
+                        })
+                        .collect(),
+                })
+                .collect(),
+        }
+    }
+}
+
+impl TryFrom<StoredDisassembly> for Disassembly {
+    // The reason the disassembly could not be restored.
+    type Error = String;
+
+    fn try_from(stored: StoredDisassembly) -> Result<Self, String> {
+        let (arch, bitness): (FileArchitecture, u32) = if stored.bits == 64 {
+            (FileArchitecture::AMD64, 64)
+        } else {
+            (FileArchitecture::I386, 32)
+        };
+
+        let graphs: Vec<ControlFlowGraph> = stored
+            .graphs
+            .into_iter()
+            .map(|graph| {
+                let blocks: Vec<BasicBlock> = graph
+                    .blocks
+                    .into_iter()
+                    .map(|block| {
+                        let instructions: Vec<Instruction> = block
+                            .instructions
+                            .iter()
+                            .map(|ins| {
+                                let invalid = |reason: String| {
+                                    format!("invalid instruction at {:#x} of {:?}: {reason}", ins.0, graph.name)
+                                };
+                                if ins.1.len() % 2 != 0 || !ins.1.bytes().all(|byte| byte.is_ascii_hexdigit()) {
+                                    return Err(invalid(format!("bytes {:?} are not hex encoded", ins.1)));
+                                }
+                                Instruction::new(arch, &bitness, ins).map_err(|error| invalid(error.to_string()))
+                            })
+                            .collect::<Result<_, String>>()?;
+                        let mut call_targets: Vec<Option<String>> = block.call_targets;
+                        call_targets.resize(instructions.len(), None);
+                        let mut basic_block = BasicBlock::with_call_targets(
+                            block.offset,
+                            &instructions,
+                            call_targets,
+                            stored.normalization,
+                        );
+                        basic_block.in_refs = block.in_refs;
+                        basic_block.out_refs = block.out_refs;
+                        Ok(basic_block)
+                    })
+                    .collect::<Result<_, String>>()?;
+
+                let mut control_flow_graph = ControlFlowGraph::with_normalization(
+                    &graph.name,
+                    graph.offset,
+                    blocks,
+                    stored.normalization,
+                );
+                control_flow_graph.is_exported = graph.is_exported;
+                Ok(control_flow_graph)
+            })
+            .collect::<Result<_, String>>()?;
+
+        Ok(Disassembly {
+            name: stored.name,
+            path: stored.path,
+            graphs,
+            format: stored.format,
+            arch: stored.arch,
+            bits: stored.bits,
+            normalization: stored.normalization,
+        })
+    }
+}
+
+/// Portable database of reference disassemblies, avoiding their disassembly on every run.
+///
+/// The database is stored as gzip compressed JSON, keeping the instructions of each graph so that
+/// it can be re-normalized to any mode once loaded.
+#[pyclass(frozen)]
+#[derive(Clone)]
+pub struct SignatureDb {
+    #[pyo3(get)]
+    disassemblies: Vec<Disassembly>,
+}
+
+impl SignatureDb {
+    /// Build a new signature database from a set of reference disassemblies.
+    pub fn build<T: Borrow<Disassembly>>(disassemblies: &[T]) -> Self {
+        Self {
+            disassemblies: disassemblies
+                .iter()
+                .map(|disassembly| disassembly.borrow().clone())
+                .collect(),
+        }
+    }
+
+    /// The reference disassemblies stored within the database.
+    #[inline]
+    pub fn disassemblies(&self) -> &Vec<Disassembly> {
+        &self.disassemblies
+    }
+
+    /// Write the signature database to the file at `path`.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        let file_error = |error: std::io::Error| Error::FileAccess {
+            path: path.to_string_lossy().to_string(),
+            reason: error.to_string(),
+        };
+
+        let stored: StoredSignatureDb = StoredSignatureDb {
+            version: SIGNATURE_DB_VERSION,
+            disassemblies: self.disassemblies.iter().map(StoredDisassembly::from).collect(),
+        };
+
+        let out_file: File = File::create(path).map_err(file_error)?;
+        let mut encoder: GzEncoder<BufWriter<File>> =
+            GzEncoder::new(BufWriter::new(out_file), Compression::default());
+        serde_json::to_writer(&mut encoder, &stored).map_err(|error| file_error(error.into()))?;
+        encoder.finish().map_err(file_error)?.flush().map_err(file_error)?;
+        Ok(())
+    }
+
+    /// Load a signature database from the file at `path`.
+    pub fn load(path: &Path) -> Result<Self, Error> {
+        let in_file: File = File::open(path).map_err(|error| Error::FileAccess {
+            path: path.to_string_lossy().to_string(),
+            reason: error.to_string(),
+        })?;
+        let invalid_db = |reason: String| Error::InvalidSignatureDb {
+            path: path.to_string_lossy().to_string(),
+            reason,
+        };
+
+        let stored: StoredSignatureDb = serde_json::from_reader(GzDecoder::new(BufReader::new(in_file)))
+            .map_err(|error| invalid_db(error.to_string()))?;
+        if stored.version != SIGNATURE_DB_VERSION {
+            return Err(invalid_db(format!("unsupported version {}", stored.version)));
+        }
+
+        Ok(Self {
+            disassemblies: stored
+                .disassemblies
+                .into_iter()
+                .map(Disassembly::try_from)
+                .collect::<Result<_, String>>()
+                .map_err(invalid_db)?,
+        })
+    }
+}
+
+#[pymethods]
+impl SignatureDb {
+    fn __repr__(&self) -> String {
+        format!("SignatureDb(references={})", self.disassemblies.len())
+    }
+
+    fn __len__(&self) -> usize {
+        self.disassemblies.len()
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "build")]
+    fn py_build(disassemblies: Vec<PyRef<Disassembly>>) -> Self {
+        SignatureDb::build(&disassemblies)
+    }
+
+    #[pyo3(name = "save")]
+    fn py_save(&self, path: PathBuf) -> PyResult<()> {
+        Ok(self.save(&path)?)
+    }
+
+    #[staticmethod]
+    #[pyo3(name = "load")]
+    fn py_load(path: PathBuf) -> PyResult<Self> {
+        Ok(SignatureDb::load(&path)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::test_utils::{chain_graph, disassembly, keyed_block};
+
+    #[test]
+    fn load_rejects_invalid_instructions() {
+        let graph: ControlFlowGraph = chain_graph("main.f", 0x1000, vec![keyed_block(0x1000, &["55", "c3"])]);
+        let mut stored: StoredDisassembly = StoredDisassembly::from(&disassembly("reference", vec![graph]));
+        // Bytes that are not hex encoded, as written by a corrupted or foreign database.
+        stored.graphs[0].blocks[0].instructions[1].1 = "zz".to_string();
+
+        let path: PathBuf = std::env::temp_dir().join(format!("gographer-invalid-{}.db", std::process::id()));
+        let stored: StoredSignatureDb = StoredSignatureDb {
+            version: SIGNATURE_DB_VERSION,
+            disassemblies: vec![stored],
+        };
+        let mut encoder: GzEncoder<File> = GzEncoder::new(File::create(&path).unwrap(), Compression::default());
+        serde_json::to_writer(&mut encoder, &stored).unwrap();
+        encoder.finish().unwrap();
+
+        let result: Result<SignatureDb, Error> = SignatureDb::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(Error::InvalidSignatureDb { reason, .. }) if reason.contains("0x1001")));
+    }
+}
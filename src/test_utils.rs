@@ -0,0 +1,67 @@
+//! Builders of synthetic disassemblies shared by the unit tests.
+
+use std::path::PathBuf;
+
+use smda::{function::Instruction, FileArchitecture};
+
+use crate::control_flow_graph::{BasicBlock, ControlFlowGraph, NormalizationMode};
+use crate::disassembly::Disassembly;
+
+/// Create an x86-64 instruction from its hex encoded bytes, mnemonic and operands.
+pub(crate) fn instruction(offset: u64, bytes: &str, mnemonic: &str, operands: &str) -> Instruction {
+    let operands: Option<String> = (!operands.is_empty()).then(|| operands.to_string());
+    Instruction::new(FileArchitecture::AMD64, &64, &(offset, bytes.to_string(), mnemonic.to_string(), operands))
+        .expect("Failed to create instruction")
+}
+
+/// Create a basic block of (bytes, mnemonic, operands) instructions, normalized with `mode`.
+pub(crate) fn block_with_mode(
+    offset: u64,
+    instructions: &[(&str, &str, &str)],
+    (in_refs, out_refs): (&[usize], &[usize]),
+    mode: NormalizationMode,
+) -> BasicBlock {
+    let instructions: Vec<Instruction> = instructions
+        .iter()
+        .enumerate()
+        .map(|(i, (bytes, mnemonic, operands))| instruction(offset + i as u64, bytes, mnemonic, operands))
+        .collect();
+    let mut block: BasicBlock = BasicBlock::new(offset, &instructions, mode);
+    block.in_refs = in_refs.to_vec();
+    block.out_refs = out_refs.to_vec();
+    block
+}
+
+/// Create a basic block of (bytes, mnemonic, operands) instructions, with the default normalization.
+pub(crate) fn block(offset: u64, instructions: &[(&str, &str, &str)], edges: (&[usize], &[usize])) -> BasicBlock {
+    block_with_mode(offset, instructions, edges, NormalizationMode::default())
+}
+
+/// Create a basic block of single byte instructions whose keys are `bytes`, without edges.
+pub(crate) fn keyed_block(offset: u64, bytes: &[&str]) -> BasicBlock {
+    let instructions: Vec<(&str, &str, &str)> = bytes.iter().map(|bytes| (*bytes, "db", "")).collect();
+    block(offset, &instructions, (&[], &[]))
+}
+
+/// Create a graph of `blocks` chained by their edges in order, with the default normalization.
+pub(crate) fn chain_graph(name: &str, offset: u64, mut blocks: Vec<BasicBlock>) -> ControlFlowGraph {
+    let count: usize = blocks.len();
+    for (index, block) in blocks.iter_mut().enumerate() {
+        block.in_refs = if index > 0 { vec![index - 1] } else { Vec::new() };
+        block.out_refs = if index + 1 < count { vec![index + 1] } else { Vec::new() };
+    }
+    ControlFlowGraph::new(name, offset, blocks)
+}
+
+/// Create a disassembly of `graphs`, normalized with the default mode.
+pub(crate) fn disassembly(name: &str, graphs: Vec<ControlFlowGraph>) -> Disassembly {
+    Disassembly {
+        name: name.to_string(),
+        path: PathBuf::from(name),
+        graphs,
+        format: "elf".to_string(),
+        arch: "amd64".to_string(),
+        bits: 64,
+        normalization: NormalizationMode::default(),
+    }
+}